@@ -0,0 +1,164 @@
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use std::io;
+
+use crate::platform::CommandOutput;
+
+/// Error returned by every Tauri command.
+///
+/// Serialized as `{ code, message }` plus the variant's details, so the
+/// frontend can branch on `code` instead of matching message text. Codes are
+/// the variant names and must stay stable.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MonitorError {
+    #[error("No battery found (desktop Mac?)")]
+    NoBattery,
+    #[error("{tool} failed: {detail}")]
+    CommandFailed {
+        tool: String,
+        /// Exit code, `None` when the tool could not be started or was killed
+        status: Option<i32>,
+        detail: String,
+    },
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("Failed to parse {0}")]
+    ParseError(String),
+    #[error("{0} is not supported on this platform")]
+    Unsupported(String),
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl MonitorError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            MonitorError::NoBattery => "NoBattery",
+            MonitorError::CommandFailed { .. } => "CommandFailed",
+            MonitorError::PermissionDenied(_) => "PermissionDenied",
+            MonitorError::ParseError(_) => "ParseError",
+            MonitorError::Unsupported(_) => "Unsupported",
+            MonitorError::InvalidArgument(_) => "InvalidArgument",
+            MonitorError::NotFound(_) => "NotFound",
+            MonitorError::Internal(_) => "Internal",
+        }
+    }
+
+    /// A tool that could not be started.
+    pub fn spawn_failed(tool: &str, err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            return MonitorError::PermissionDenied(format!("cannot run {}", tool));
+        }
+        MonitorError::CommandFailed {
+            tool: tool.to_string(),
+            status: None,
+            detail: err.to_string(),
+        }
+    }
+
+    /// A tool that ran but exited unsuccessfully.
+    pub fn command_failed(tool: &str, output: &CommandOutput) -> Self {
+        let stderr = output.stderr.trim();
+        if stderr.contains("Operation not permitted") {
+            return MonitorError::PermissionDenied(stderr.to_string());
+        }
+        MonitorError::CommandFailed {
+            tool: tool.to_string(),
+            status: output.status,
+            detail: if stderr.is_empty() {
+                match output.status {
+                    Some(code) => format!("exited with status {}", code),
+                    None => "terminated by a signal".to_string(),
+                }
+            } else {
+                stderr.to_string()
+            },
+        }
+    }
+}
+
+impl From<io::Error> for MonitorError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => MonitorError::PermissionDenied(err.to_string()),
+            _ => MonitorError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl Serialize for MonitorError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MonitorError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let MonitorError::CommandFailed { tool, status, .. } = self {
+            state.serialize_field("tool", tool)?;
+            state.serialize_field("status", status)?;
+        }
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let json = serde_json::to_value(MonitorError::NoBattery).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "NoBattery",
+                "message": "No battery found (desktop Mac?)",
+            })
+        );
+    }
+
+    #[test]
+    fn test_serializes_command_details() {
+        let output = CommandOutput {
+            status: Some(1),
+            stdout: String::new(),
+            stderr: "ps: illegal option -- m\n".to_string(),
+        };
+        let json = serde_json::to_value(MonitorError::command_failed("ps", &output)).unwrap();
+        assert_eq!(json["code"], "CommandFailed");
+        assert_eq!(json["tool"], "ps");
+        assert_eq!(json["status"], 1);
+        assert_eq!(json["message"], "ps failed: ps: illegal option -- m");
+    }
+
+    #[test]
+    fn test_permission_errors_are_classified() {
+        let output = CommandOutput {
+            status: Some(1),
+            stdout: String::new(),
+            stderr: "ps: Operation not permitted".to_string(),
+        };
+        assert_eq!(
+            MonitorError::command_failed("ps", &output).code(),
+            "PermissionDenied"
+        );
+
+        let err = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(
+            MonitorError::spawn_failed("du", err).code(),
+            "PermissionDenied"
+        );
+
+        let err = io::Error::new(io::ErrorKind::NotFound, "missing");
+        assert_eq!(
+            MonitorError::spawn_failed("du", err),
+            MonitorError::CommandFailed {
+                tool: "du".to_string(),
+                status: None,
+                detail: "missing".to_string(),
+            }
+        );
+    }
+}
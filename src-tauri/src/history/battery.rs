@@ -0,0 +1,574 @@
+//! Battery samples persisted across launches, and the charging and
+//! discharging sessions derived from them.
+//!
+//! Samples are appended to a JSON Lines file as the sampler takes them, so a
+//! crash loses at most the sample being written.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::State;
+
+use crate::error::MonitorError;
+use crate::monitors::battery::{BatteryInfo, EstimateConfidence, TimeEstimate};
+use crate::sampler::now_ms;
+
+use super::{fit_line, store};
+
+/// History shared between the sampler and the commands that query it.
+pub type SharedBatteryHistory = Arc<BatteryHistory>;
+
+const MINUTE_MS: u64 = 60 * 1000;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// Samples closer together than this are dropped, unless the power state
+/// changed in between.
+const RECORD_INTERVAL_MS: u64 = MINUTE_MS;
+
+/// How long samples are kept.
+const RETENTION_MS: u64 = 30 * DAY_MS;
+
+/// A gap this long between samples (the app wasn't running, or the Mac was
+/// asleep) ends a session.
+const MAX_SAMPLE_GAP_MS: u64 = 15 * MINUTE_MS;
+
+/// The charge rate is smoothed over samples this recent.
+const ESTIMATE_WINDOW_MS: u64 = 15 * MINUTE_MS;
+
+/// The window needs to span this long before its rate is trusted.
+const MIN_ESTIMATE_SPAN_MS: u64 = 5 * MINUTE_MS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatterySample {
+    pub timestamp_ms: u64,
+    pub percentage: f32,
+    pub is_charging: bool,
+    pub is_plugged_in: bool,
+    pub power_draw_watts: Option<f32>,
+    pub temperature_celsius: Option<f32>,
+}
+
+impl BatterySample {
+    pub fn from_info(timestamp_ms: u64, info: &BatteryInfo) -> Self {
+        BatterySample {
+            timestamp_ms,
+            percentage: info.percentage,
+            is_charging: info.is_charging,
+            is_plugged_in: info.is_plugged_in,
+            power_draw_watts: info.power_draw_watts,
+            temperature_celsius: info.temperature_celsius,
+        }
+    }
+
+    /// The session this sample belongs to. Plugged in but not charging (full,
+    /// or held at a charge limit) is neither.
+    fn session_kind(&self) -> Option<SessionKind> {
+        if self.is_charging {
+            Some(SessionKind::Charging)
+        } else if !self.is_plugged_in {
+            Some(SessionKind::Discharging)
+        } else {
+            None
+        }
+    }
+
+    fn same_power_state(&self, other: &BatterySample) -> bool {
+        self.is_charging == other.is_charging && self.is_plugged_in == other.is_plugged_in
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionKind {
+    Charging,
+    Discharging,
+}
+
+impl SessionKind {
+    /// Whether a charge rate goes the way this kind of session does.
+    fn moves_with(self, rate: f32) -> bool {
+        match self {
+            SessionKind::Charging => rate > 0.0,
+            SessionKind::Discharging => rate < 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatterySession {
+    pub kind: SessionKind,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub duration_minutes: u32,
+    pub start_percentage: f32,
+    pub end_percentage: f32,
+    /// Mean power draw over the session, when the platform reports it
+    pub average_watts: Option<f32>,
+}
+
+pub struct BatteryHistory {
+    /// Where samples are persisted, `None` to keep them in memory only
+    path: Option<PathBuf>,
+    samples: Mutex<Vec<BatterySample>>,
+}
+
+impl BatteryHistory {
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            samples: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load the history stored at `path`. Unreadable lines are skipped.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut samples: Vec<BatterySample> = store::load(&path);
+        samples.sort_by_key(|sample| sample.timestamp_ms);
+
+        let history = Self {
+            path: Some(path),
+            samples: Mutex::new(Vec::new()),
+        };
+        history.compact(&mut samples, now_ms());
+        *history.samples() = samples;
+        history
+    }
+
+    /// History in the user's local data directory.
+    pub fn open_default() -> Self {
+        match store::default_path("battery-history.jsonl") {
+            Some(path) => Self::open(path),
+            None => Self::in_memory(),
+        }
+    }
+
+    fn samples(&self) -> MutexGuard<'_, Vec<BatterySample>> {
+        match self.samples.lock() {
+            Ok(samples) => samples,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Record a sample, returning whether it was kept.
+    pub fn record(&self, sample: BatterySample) -> bool {
+        let mut samples = self.samples();
+        if let Some(last) = samples.last() {
+            if sample.timestamp_ms < last.timestamp_ms {
+                return false;
+            }
+            if sample.same_power_state(last)
+                && sample.timestamp_ms - last.timestamp_ms < RECORD_INTERVAL_MS
+            {
+                return false;
+            }
+        }
+
+        if let Some(path) = &self.path {
+            store::append(path, &sample);
+        }
+        let now = sample.timestamp_ms;
+        samples.push(sample);
+
+        // Rewriting the file is expensive, so let a day's worth of expired
+        // samples build up first
+        if samples
+            .first()
+            .is_some_and(|first| first.timestamp_ms + RETENTION_MS + DAY_MS < now)
+        {
+            self.compact(&mut samples, now);
+        }
+        true
+    }
+
+    /// Drop samples past retention, rewriting the file if any were dropped.
+    fn compact(&self, samples: &mut Vec<BatterySample>, now: u64) {
+        let cutoff = now.saturating_sub(RETENTION_MS);
+        let before = samples.len();
+        samples.retain(|sample| sample.timestamp_ms >= cutoff);
+        if samples.len() == before {
+            return;
+        }
+
+        if let Some(path) = &self.path {
+            store::rewrite(path, samples);
+        }
+    }
+
+    pub fn samples_between(&self, from_ms: u64, to_ms: u64) -> Vec<BatterySample> {
+        self.samples()
+            .iter()
+            .filter(|sample| (from_ms..=to_ms).contains(&sample.timestamp_ms))
+            .cloned()
+            .collect()
+    }
+
+    /// The sample closest to `timestamp_ms`, if one is within `within_ms`.
+    pub fn sample_near(&self, timestamp_ms: u64, within_ms: u64) -> Option<BatterySample> {
+        self.samples()
+            .iter()
+            .filter(|sample| sample.timestamp_ms.abs_diff(timestamp_ms) <= within_ms)
+            .min_by_key(|sample| sample.timestamp_ms.abs_diff(timestamp_ms))
+            .cloned()
+    }
+
+    /// Estimate the time to full or empty from the charge rate over the
+    /// last few minutes, falling back to the rate of the previous session of
+    /// the same kind right after plugging or unplugging.
+    pub fn estimate_time_remaining(
+        &self,
+        timestamp_ms: u64,
+        info: &BatteryInfo,
+    ) -> Option<TimeEstimate> {
+        estimate(
+            &self.samples(),
+            &BatterySample::from_info(timestamp_ms, info),
+        )
+    }
+
+    /// Sessions overlapping the range, oldest first.
+    pub fn sessions_between(&self, from_ms: u64, to_ms: u64) -> Vec<BatterySession> {
+        derive_sessions(&self.samples())
+            .into_iter()
+            .filter(|session| session.end_ms >= from_ms && session.start_ms <= to_ms)
+            .collect()
+    }
+}
+
+/// Split samples into runs of the same power state. A run ends when the
+/// state changes, at the first sample of the new state, or at a gap in the
+/// samples, at the last sample before it.
+pub fn derive_sessions(samples: &[BatterySample]) -> Vec<BatterySession> {
+    let mut sessions = Vec::new();
+    let mut start = 0;
+
+    for i in 1..=samples.len() {
+        let gap = i < samples.len()
+            && samples[i].timestamp_ms - samples[i - 1].timestamp_ms > MAX_SAMPLE_GAP_MS;
+        let changed = i < samples.len() && !samples[i].same_power_state(&samples[start]);
+        if i < samples.len() && !gap && !changed {
+            continue;
+        }
+
+        let run = &samples[start..i];
+        let end = if changed && !gap {
+            &samples[i]
+        } else {
+            &samples[i - 1]
+        };
+        if let Some(session) = session_from(run, end) {
+            sessions.push(session);
+        }
+        start = i;
+    }
+
+    sessions
+}
+
+fn session_from(run: &[BatterySample], end: &BatterySample) -> Option<BatterySession> {
+    let first = run.first()?;
+    let kind = first.session_kind()?;
+    if end.timestamp_ms <= first.timestamp_ms {
+        return None;
+    }
+
+    let watts: Vec<f32> = run.iter().filter_map(|s| s.power_draw_watts).collect();
+    let average_watts = if watts.is_empty() {
+        None
+    } else {
+        Some(watts.iter().sum::<f32>() / watts.len() as f32)
+    };
+
+    Some(BatterySession {
+        kind,
+        start_ms: first.timestamp_ms,
+        end_ms: end.timestamp_ms,
+        duration_minutes: ((end.timestamp_ms - first.timestamp_ms) / MINUTE_MS) as u32,
+        start_percentage: first.percentage,
+        end_percentage: end.percentage,
+        average_watts,
+    })
+}
+
+fn estimate(samples: &[BatterySample], current: &BatterySample) -> Option<TimeEstimate> {
+    let kind = current.session_kind()?;
+    let earlier = &samples[..samples.partition_point(|s| s.timestamp_ms < current.timestamp_ms)];
+
+    // The trailing run of samples in the current power state
+    let mut window = vec![current];
+    for sample in earlier.iter().rev() {
+        let next = window[window.len() - 1];
+        if !sample.same_power_state(current)
+            || next.timestamp_ms - sample.timestamp_ms > MAX_SAMPLE_GAP_MS
+            || current.timestamp_ms - sample.timestamp_ms > ESTIMATE_WINDOW_MS
+        {
+            break;
+        }
+        window.push(sample);
+    }
+    let oldest = window[window.len() - 1];
+
+    let measured = if current.timestamp_ms - oldest.timestamp_ms >= MIN_ESTIMATE_SPAN_MS {
+        let points: Vec<(f64, f64)> = window
+            .iter()
+            .map(|s| {
+                (
+                    (s.timestamp_ms - oldest.timestamp_ms) as f64 / HOUR_MS as f64,
+                    s.percentage as f64,
+                )
+            })
+            .collect();
+        fit_line(&points).map(|(slope, _)| slope as f32)
+    } else {
+        None
+    };
+
+    let (rate, confidence) = match measured.filter(|&rate| kind.moves_with(rate)) {
+        Some(rate) => (rate, EstimateConfidence::High),
+        None => {
+            let rate = derive_sessions(earlier)
+                .into_iter()
+                .rev()
+                .find(|session| session.kind == kind && session.duration_minutes > 0)
+                .map(|session| {
+                    (session.end_percentage - session.start_percentage) * 60.0
+                        / session.duration_minutes as f32
+                })
+                .filter(|&rate| kind.moves_with(rate))?;
+            (rate, EstimateConfidence::Low)
+        }
+    };
+
+    let remaining = match kind {
+        SessionKind::Charging => 100.0 - current.percentage,
+        SessionKind::Discharging => current.percentage,
+    };
+    Some(TimeEstimate {
+        minutes: (remaining / rate.abs() * 60.0).round() as u32,
+        is_charging: kind == SessionKind::Charging,
+        confidence,
+        rate_percentage_per_hour: rate,
+    })
+}
+
+pub(crate) fn time_range(
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+) -> Result<(u64, u64), MonitorError> {
+    let from_ms = from_ms.unwrap_or(0);
+    let to_ms = to_ms.unwrap_or(u64::MAX);
+    if from_ms > to_ms {
+        return Err(MonitorError::InvalidArgument(
+            "from_ms must not be after to_ms".to_string(),
+        ));
+    }
+    Ok((from_ms, to_ms))
+}
+
+#[tauri::command]
+pub fn get_battery_history(
+    history: State<'_, SharedBatteryHistory>,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+) -> Result<Vec<BatterySample>, MonitorError> {
+    let (from_ms, to_ms) = time_range(from_ms, to_ms)?;
+    Ok(history.samples_between(from_ms, to_ms))
+}
+
+#[tauri::command]
+pub fn get_battery_sessions(
+    history: State<'_, SharedBatteryHistory>,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+) -> Result<Vec<BatterySession>, MonitorError> {
+    let (from_ms, to_ms) = time_range(from_ms, to_ms)?;
+    Ok(history.sessions_between(from_ms, to_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn sample(
+        minute: u64,
+        percentage: f32,
+        is_charging: bool,
+        is_plugged_in: bool,
+    ) -> BatterySample {
+        BatterySample {
+            timestamp_ms: minute * MINUTE_MS,
+            percentage,
+            is_charging,
+            is_plugged_in,
+            power_draw_watts: None,
+            temperature_celsius: None,
+        }
+    }
+
+    fn discharging(minute: u64, percentage: f32, watts: f32) -> BatterySample {
+        BatterySample {
+            power_draw_watts: Some(watts),
+            ..sample(minute, percentage, false, false)
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("mac-health-{}-{}.jsonl", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_sessions_split_on_power_state() {
+        let samples = vec![
+            discharging(0, 90.0, 8.0),
+            discharging(5, 86.0, 12.0),
+            discharging(10, 82.0, 10.0),
+            sample(12, 81.0, true, true),
+            sample(25, 90.0, true, true),
+            sample(38, 99.0, true, true),
+            // Full: plugged in but not charging
+            sample(50, 100.0, false, true),
+            sample(60, 100.0, false, true),
+        ];
+        let sessions = derive_sessions(&samples);
+        assert_eq!(sessions.len(), 2);
+
+        assert_eq!(sessions[0].kind, SessionKind::Discharging);
+        assert_eq!(sessions[0].duration_minutes, 12);
+        assert_eq!(sessions[0].start_percentage, 90.0);
+        assert_eq!(sessions[0].end_percentage, 81.0);
+        assert_eq!(sessions[0].average_watts, Some(10.0));
+
+        assert_eq!(sessions[1].kind, SessionKind::Charging);
+        assert_eq!(sessions[1].duration_minutes, 38);
+        assert_eq!(sessions[1].end_percentage, 100.0);
+        assert_eq!(sessions[1].average_watts, None);
+    }
+
+    #[test]
+    fn test_gap_ends_session() {
+        let samples = vec![
+            discharging(0, 90.0, 8.0),
+            discharging(10, 85.0, 8.0),
+            // Asleep for an hour
+            discharging(70, 83.0, 8.0),
+            discharging(80, 78.0, 8.0),
+        ];
+        let sessions = derive_sessions(&samples);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].end_percentage, 85.0);
+        assert_eq!(sessions[1].start_percentage, 83.0);
+    }
+
+    #[test]
+    fn test_record_throttles_unchanged_state() {
+        let history = BatteryHistory::in_memory();
+        assert!(history.record(discharging(0, 90.0, 8.0)));
+        // Ten seconds later
+        let mut soon = discharging(0, 90.0, 8.0);
+        soon.timestamp_ms += 10_000;
+        assert!(!history.record(soon.clone()));
+        // Unplugging is recorded straight away
+        soon.is_charging = true;
+        soon.is_plugged_in = true;
+        assert!(history.record(soon));
+        assert!(history.record(sample(2, 91.0, true, true)));
+        assert_eq!(history.samples_between(0, u64::MAX).len(), 3);
+    }
+
+    #[test]
+    fn test_history_persists_and_expires() {
+        let path = temp_path("battery-history");
+        let now = now_ms();
+        let history = BatteryHistory::open(&path);
+        let mut old = discharging(0, 50.0, 5.0);
+        old.timestamp_ms = now - RETENTION_MS - DAY_MS;
+        let mut recent = discharging(0, 40.0, 5.0);
+        recent.timestamp_ms = now;
+        assert!(history.record(old));
+        assert!(history.record(recent.clone()));
+
+        let reopened = BatteryHistory::open(&path);
+        assert_eq!(reopened.samples_between(0, u64::MAX), vec![recent]);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_sessions_between_filters_by_overlap() {
+        let history = BatteryHistory::in_memory();
+        for s in [
+            discharging(0, 90.0, 8.0),
+            discharging(10, 80.0, 8.0),
+            sample(20, 79.0, true, true),
+            sample(30, 95.0, true, true),
+            discharging(40, 95.0, 8.0),
+            discharging(50, 90.0, 8.0),
+        ] {
+            history.record(s);
+        }
+        let sessions = history.sessions_between(22 * MINUTE_MS, 28 * MINUTE_MS);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].kind, SessionKind::Charging);
+    }
+
+    #[test]
+    fn test_estimate_from_recent_rate() {
+        // Losing half a point a minute
+        let samples: Vec<BatterySample> = (0..10)
+            .map(|minute| discharging(minute, 50.0 - minute as f32 * 0.5, 8.0))
+            .collect();
+        let estimate = estimate(&samples, &discharging(10, 45.0, 8.0)).unwrap();
+        assert_eq!(estimate.confidence, EstimateConfidence::High);
+        assert!(!estimate.is_charging);
+        assert!((estimate.rate_percentage_per_hour + 30.0).abs() < 0.01);
+        assert_eq!(estimate.minutes, 90);
+    }
+
+    #[test]
+    fn test_estimate_right_after_unplugging_uses_last_session() {
+        let samples = vec![
+            discharging(0, 80.0, 8.0),
+            discharging(10, 75.0, 8.0),
+            discharging(20, 70.0, 8.0),
+            sample(25, 69.0, true, true),
+            sample(35, 75.0, true, true),
+            discharging(40, 76.0, 8.0),
+        ];
+        // A minute after unplugging: the previous discharge lost 11 points
+        // in 25 minutes
+        let unplugged = estimate(&samples, &discharging(41, 76.0, 8.0)).unwrap();
+        assert_eq!(unplugged.confidence, EstimateConfidence::Low);
+        assert!((unplugged.rate_percentage_per_hour + 26.4).abs() < 0.01);
+        assert_eq!(unplugged.minutes, 173);
+
+        // Plugged in again: the previous charge gained 7 points in 15 minutes
+        let plugged = estimate(&samples, &sample(41, 76.0, true, true)).unwrap();
+        assert_eq!(plugged.confidence, EstimateConfidence::Low);
+        assert!(plugged.is_charging);
+        assert_eq!(plugged.minutes, 51);
+    }
+
+    #[test]
+    fn test_no_estimate_without_history_or_when_full() {
+        assert!(estimate(&[], &discharging(0, 90.0, 8.0)).is_none());
+        let samples = vec![
+            sample(0, 100.0, false, true),
+            sample(10, 100.0, false, true),
+        ];
+        assert!(estimate(&samples, &sample(20, 100.0, false, true)).is_none());
+    }
+
+    #[test]
+    fn test_time_range_validation() {
+        assert_eq!(time_range(None, None).unwrap(), (0, u64::MAX));
+        assert_eq!(
+            time_range(Some(10), Some(5)).unwrap_err().code(),
+            "InvalidArgument"
+        );
+    }
+}
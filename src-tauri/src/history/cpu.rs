@@ -0,0 +1,437 @@
+//! Recent CPU usage and load average, kept in memory for the sparklines in
+//! the CPU view.
+//!
+//! Samples are averaged into fixed-width slots at two resolutions: one slot
+//! per CPU sampling interval for the last ten minutes, and one minute for
+//! the last day. The fine slots follow the interval the sampler runs at, so
+//! each normally holds exactly one sample, and start over when it changes.
+//! Every slot in the window has a point, oldest first. Slots no sample
+//! landed in, e.g. while the sampler was held up, have a `sample_count` of
+//! zero and no values.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::State;
+
+use crate::monitors::cpu::{CpuInfo, LoadAverage};
+
+/// History shared between the sampler and the command that queries it.
+pub type SharedCpuHistory = Arc<CpuHistory>;
+
+const SECOND_MS: u64 = 1000;
+const MINUTE_MS: u64 = 60 * SECOND_MS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CpuHistoryResolution {
+    /// One slot per CPU sampling interval
+    Sample,
+    Minute,
+}
+
+impl CpuHistoryResolution {
+    fn retention_ms(self) -> u64 {
+        match self {
+            CpuHistoryResolution::Sample => 10 * MINUTE_MS,
+            CpuHistoryResolution::Minute => 24 * 60 * MINUTE_MS,
+        }
+    }
+}
+
+/// The points of one resolution and how wide their slots are.
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuHistorySeries {
+    pub slot_ms: u64,
+    pub points: Vec<CpuHistoryPoint>,
+}
+
+/// The average of the samples taken within one slot.
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuHistoryPoint {
+    /// Start of the slot
+    pub timestamp_ms: u64,
+    /// Zero when no sample landed in the slot
+    pub sample_count: u32,
+    /// `None` when every sample in the slot was only an estimate
+    pub total_usage_percentage: Option<f32>,
+    /// Empty when per-core usage couldn't be measured
+    pub per_core_usage: Vec<f32>,
+    /// `None` only for empty slots
+    pub load_average: Option<LoadAverage>,
+}
+
+impl CpuHistoryPoint {
+    fn empty(timestamp_ms: u64) -> Self {
+        Self {
+            timestamp_ms,
+            sample_count: 0,
+            total_usage_percentage: None,
+            per_core_usage: Vec::new(),
+            load_average: None,
+        }
+    }
+}
+
+/// Running sums for the slot samples are still being added to.
+struct Bucket {
+    start_ms: u64,
+    samples: u32,
+    total_sum: f32,
+    total_samples: u32,
+    per_core_sum: Vec<f32>,
+    per_core_samples: u32,
+    load_sum: [f64; 3],
+}
+
+impl Bucket {
+    fn new(start_ms: u64) -> Self {
+        Self {
+            start_ms,
+            samples: 0,
+            total_sum: 0.0,
+            total_samples: 0,
+            per_core_sum: Vec::new(),
+            per_core_samples: 0,
+            load_sum: [0.0; 3],
+        }
+    }
+
+    fn add(&mut self, info: &CpuInfo) {
+        self.samples += 1;
+        // Invented numbers would skew the average
+        if !info.is_estimate {
+            self.total_sum += info.total_usage_percentage;
+            self.total_samples += 1;
+        }
+
+        let per_core = info.per_core_usage.iter().map(|core| core.usage_percentage);
+        if self.per_core_sum.len() == info.per_core_usage.len() {
+            for (sum, usage) in self.per_core_sum.iter_mut().zip(per_core) {
+                *sum += usage;
+            }
+            self.per_core_samples += 1;
+        } else {
+            // The core count changed, e.g. cores were taken offline
+            self.per_core_sum = per_core.collect();
+            self.per_core_samples = 1;
+        }
+
+        let load = &info.load_average;
+        self.load_sum[0] += load.one_minute;
+        self.load_sum[1] += load.five_minutes;
+        self.load_sum[2] += load.fifteen_minutes;
+    }
+
+    fn point(&self) -> CpuHistoryPoint {
+        let samples = self.samples.max(1) as f64;
+        CpuHistoryPoint {
+            timestamp_ms: self.start_ms,
+            sample_count: self.samples,
+            total_usage_percentage: (self.total_samples > 0)
+                .then(|| self.total_sum / self.total_samples as f32),
+            per_core_usage: self
+                .per_core_sum
+                .iter()
+                .map(|sum| sum / self.per_core_samples as f32)
+                .collect(),
+            load_average: Some(LoadAverage {
+                one_minute: self.load_sum[0] / samples,
+                five_minutes: self.load_sum[1] / samples,
+                fifteen_minutes: self.load_sum[2] / samples,
+            }),
+        }
+    }
+}
+
+/// Finished slots at one resolution, oldest first and one slot apart, and
+/// the one being filled.
+struct Series {
+    retention_ms: u64,
+    bucket_ms: u64,
+    points: VecDeque<CpuHistoryPoint>,
+    current: Option<Bucket>,
+}
+
+impl Series {
+    fn new(resolution: CpuHistoryResolution, bucket_ms: u64) -> Self {
+        let mut series = Self {
+            retention_ms: resolution.retention_ms(),
+            bucket_ms,
+            points: VecDeque::new(),
+            current: None,
+        };
+        series.points.reserve(series.capacity());
+        series
+    }
+
+    /// Number of slots kept, including the one still being filled.
+    fn capacity(&self) -> usize {
+        (self.retention_ms / self.bucket_ms).max(1) as usize
+    }
+
+    /// Switch to slots `bucket_ms` wide. Points of the old width can't be
+    /// split or merged into the new one, so they're dropped.
+    fn set_bucket_ms(&mut self, bucket_ms: u64) {
+        if bucket_ms == self.bucket_ms {
+            return;
+        }
+        *self = Self {
+            retention_ms: self.retention_ms,
+            bucket_ms,
+            points: VecDeque::new(),
+            current: None,
+        };
+        self.points.reserve(self.capacity());
+    }
+
+    fn record(&mut self, timestamp_ms: u64, info: &CpuInfo) {
+        let bucket_ms = self.bucket_ms;
+        let capacity = self.capacity();
+        let start_ms = timestamp_ms - timestamp_ms % bucket_ms;
+        match &mut self.current {
+            Some(bucket) if bucket.start_ms == start_ms => {
+                bucket.add(info);
+                return;
+            }
+            // The clock went backwards
+            Some(bucket) if bucket.start_ms > start_ms => return,
+            _ => {}
+        }
+
+        if let Some(finished) = self.current.take() {
+            // Fill the slots no sample landed in, at most a full window's worth
+            let missing = ((start_ms - finished.start_ms) / bucket_ms - 1).min(capacity as u64);
+            self.points.push_back(finished.point());
+            for slot in (1..=missing).rev() {
+                self.points
+                    .push_back(CpuHistoryPoint::empty(start_ms - slot * bucket_ms));
+            }
+        }
+        let mut bucket = Bucket::new(start_ms);
+        bucket.add(info);
+        self.current = Some(bucket);
+
+        // Leave room for the current slot
+        while self.points.len() >= capacity {
+            self.points.pop_front();
+        }
+    }
+
+    fn series(&self) -> CpuHistorySeries {
+        CpuHistorySeries {
+            slot_ms: self.bucket_ms,
+            points: self
+                .points
+                .iter()
+                .cloned()
+                .chain(self.current.as_ref().map(Bucket::point))
+                .collect(),
+        }
+    }
+}
+
+struct Tiers {
+    samples: Series,
+    minutes: Series,
+}
+
+pub struct CpuHistory {
+    tiers: Mutex<Tiers>,
+}
+
+impl Default for CpuHistory {
+    fn default() -> Self {
+        Self {
+            tiers: Mutex::new(Tiers {
+                // Sized to the real interval by the first sample
+                samples: Series::new(CpuHistoryResolution::Sample, SECOND_MS),
+                minutes: Series::new(CpuHistoryResolution::Minute, MINUTE_MS),
+            }),
+        }
+    }
+}
+
+impl CpuHistory {
+    fn tiers(&self) -> MutexGuard<'_, Tiers> {
+        match self.tiers.lock() {
+            Ok(tiers) => tiers,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Record a sample taken by a sampler running every `interval`.
+    pub fn record(&self, timestamp_ms: u64, interval: Duration, info: &CpuInfo) {
+        let mut tiers = self.tiers();
+        tiers
+            .samples
+            .set_bucket_ms(interval.as_millis().max(1) as u64);
+        tiers.samples.record(timestamp_ms, info);
+        tiers.minutes.record(timestamp_ms, info);
+    }
+
+    /// Points at `resolution`, oldest first. The last one may still be
+    /// filling up.
+    pub fn series(&self, resolution: CpuHistoryResolution) -> CpuHistorySeries {
+        let tiers = self.tiers();
+        match resolution {
+            CpuHistoryResolution::Sample => tiers.samples.series(),
+            CpuHistoryResolution::Minute => tiers.minutes.series(),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_cpu_history(
+    history: State<'_, SharedCpuHistory>,
+    resolution: Option<CpuHistoryResolution>,
+) -> CpuHistorySeries {
+    history.series(resolution.unwrap_or(CpuHistoryResolution::Sample))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitors::cpu::cpu_info;
+    use crate::platform::testing::FakeProbe;
+    use crate::platform::{CpuUsageReading, LoadReading};
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn info(total: f32, per_core: Vec<f32>, load: f64) -> CpuInfo {
+        cpu_info(&FakeProbe {
+            cpu_usage: Some(CpuUsageReading {
+                total_percentage: total,
+                per_core,
+                ..Default::default()
+            }),
+            load: LoadReading {
+                one: load,
+                five: load,
+                fifteen: load,
+            },
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_samples_are_averaged_per_slot() {
+        let history = CpuHistory::default();
+        history.record(10_000, SECOND, &info(20.0, vec![10.0, 30.0], 1.0));
+        history.record(10_500, SECOND, &info(40.0, vec![30.0, 50.0], 2.0));
+        history.record(12_200, SECOND, &info(50.0, vec![50.0, 50.0], 3.0));
+
+        let points = history.series(CpuHistoryResolution::Sample).points;
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].timestamp_ms, 10_000);
+        assert_eq!(points[0].sample_count, 2);
+        assert_eq!(points[0].total_usage_percentage, Some(30.0));
+        assert_eq!(points[0].per_core_usage, vec![20.0, 40.0]);
+        assert_eq!(points[0].load_average.as_ref().unwrap().one_minute, 1.5);
+        // The second nothing was sampled in is kept as an empty slot
+        assert_eq!(points[1].timestamp_ms, 11_000);
+        assert_eq!(points[1].sample_count, 0);
+        assert_eq!(points[1].total_usage_percentage, None);
+        assert!(points[1].load_average.is_none());
+        // The slot still being filled is included
+        assert_eq!(points[2].timestamp_ms, 12_000);
+        assert_eq!(points[2].total_usage_percentage, Some(50.0));
+
+        let points = history.series(CpuHistoryResolution::Minute).points;
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].timestamp_ms, 0);
+        assert_eq!(points[0].sample_count, 3);
+        assert!((points[0].total_usage_percentage.unwrap() - 36.67).abs() < 0.01);
+        assert_eq!(
+            points[0].load_average.as_ref().unwrap().fifteen_minutes,
+            2.0
+        );
+    }
+
+    #[test]
+    fn test_window_is_fixed_size() {
+        let history = CpuHistory::default();
+        for minute in 0..=30 {
+            history.record(minute * MINUTE_MS, SECOND, &info(10.0, vec![], 0.5));
+        }
+
+        let seconds = history.series(CpuHistoryResolution::Sample).points;
+        assert_eq!(seconds.len(), 600);
+        assert_eq!(seconds[0].timestamp_ms, 30 * MINUTE_MS - 599 * SECOND_MS);
+        assert_eq!(seconds.iter().filter(|p| p.sample_count > 0).count(), 10);
+        assert!(seconds
+            .windows(2)
+            .all(|pair| pair[1].timestamp_ms - pair[0].timestamp_ms == SECOND_MS));
+
+        let minutes = history.series(CpuHistoryResolution::Minute).points;
+        assert_eq!(minutes.len(), 31);
+        assert!(minutes[0].per_core_usage.is_empty());
+
+        // After a day asleep only the latest sample is left
+        history.record(25 * 60 * MINUTE_MS, SECOND, &info(10.0, vec![], 0.5));
+        let minutes = history.series(CpuHistoryResolution::Minute).points;
+        assert_eq!(minutes.len(), 1440);
+        assert_eq!(
+            minutes[0].timestamp_ms,
+            25 * 60 * MINUTE_MS - 1439 * MINUTE_MS
+        );
+        assert_eq!(minutes.iter().filter(|p| p.sample_count > 0).count(), 1);
+        assert_eq!(minutes[1439].sample_count, 1);
+    }
+
+    #[test]
+    fn test_fine_slots_follow_the_sampling_interval() {
+        let history = CpuHistory::default();
+        let interval = Duration::from_secs(3);
+        for sample in 0..400 {
+            history.record(sample * 3_000, interval, &info(10.0, vec![], 0.5));
+        }
+
+        let series = history.series(CpuHistoryResolution::Sample);
+        assert_eq!(series.slot_ms, 3_000);
+        assert_eq!(series.points.len(), 200);
+        assert!(series.points.iter().all(|p| p.sample_count == 1));
+
+        // A new interval starts the fine tier over but keeps the minutes
+        history.record(1_200_000, SECOND, &info(20.0, vec![], 0.5));
+        let series = history.series(CpuHistoryResolution::Sample);
+        assert_eq!(series.slot_ms, SECOND_MS);
+        assert_eq!(series.points.len(), 1);
+        assert_eq!(series.points[0].total_usage_percentage, Some(20.0));
+        assert_eq!(
+            history.series(CpuHistoryResolution::Minute).points.len(),
+            21
+        );
+    }
+
+    #[test]
+    fn test_estimates_are_left_out_of_the_usage() {
+        let history = CpuHistory::default();
+        let estimate = cpu_info(&FakeProbe {
+            load: LoadReading {
+                one: 4.0,
+                five: 4.0,
+                fifteen: 4.0,
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(estimate.is_estimate);
+
+        history.record(1_000, SECOND, &estimate);
+        let points = history.series(CpuHistoryResolution::Sample).points;
+        assert_eq!(points[0].total_usage_percentage, None);
+        assert_eq!(points[0].load_average.as_ref().unwrap().one_minute, 4.0);
+
+        history.record(1_200, SECOND, &info(10.0, vec![10.0], 2.0));
+        let points = history.series(CpuHistoryResolution::Sample).points;
+        assert_eq!(points[0].total_usage_percentage, Some(10.0));
+        assert_eq!(points[0].load_average.as_ref().unwrap().one_minute, 3.0);
+
+        // A sample from before the current bucket is ignored
+        history.record(500, SECOND, &info(90.0, vec![90.0], 2.0));
+        assert_eq!(history.series(CpuHistoryResolution::Sample).points.len(), 1);
+    }
+}
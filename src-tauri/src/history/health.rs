@@ -0,0 +1,276 @@
+//! Battery health over the life of the battery, and a forecast of when it
+//! will cross the thresholds `get_battery_info` bases its condition on.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::State;
+
+use crate::monitors::battery::{
+    BatteryInfo, REPLACE_SOON_PERCENTAGE, SERVICE_RECOMMENDED_PERCENTAGE,
+};
+
+use super::{fit_line, store};
+
+/// History shared between the sampler and the forecast command.
+pub type SharedHealthHistory = Arc<HealthHistory>;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Health moves over weeks, so one sample a day is plenty.
+const RECORD_INTERVAL_MS: u64 = DAY_MS;
+
+/// Over a shorter span the trend is mostly gauge recalibration noise.
+const MIN_TREND_SPAN_MS: u64 = 14 * DAY_MS;
+
+/// A rise in health this large means the battery was replaced.
+const REPLACEMENT_JUMP_PERCENTAGE: f32 = 10.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthSample {
+    pub timestamp_ms: u64,
+    pub max_capacity_percentage: f32,
+    pub cycle_count: Option<u32>,
+}
+
+impl HealthSample {
+    pub fn from_info(timestamp_ms: u64, info: &BatteryInfo) -> Self {
+        HealthSample {
+            timestamp_ms,
+            max_capacity_percentage: info.max_capacity_percentage,
+            cycle_count: info.cycle_count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthForecast {
+    /// Samples of the current battery, oldest first
+    pub samples: Vec<HealthSample>,
+    /// Percentage points lost every 30 days, per the trend line
+    pub loss_per_month_percentage: Option<f32>,
+    /// Percentage points lost every 100 charge cycles
+    pub loss_per_100_cycles_percentage: Option<f32>,
+    /// When the trend line crosses into "Service Recommended", in the past
+    /// if it already has
+    pub service_recommended_at_ms: Option<u64>,
+    /// When the trend line crosses into "Replace Soon"
+    pub replace_soon_at_ms: Option<u64>,
+}
+
+pub struct HealthHistory {
+    /// Where samples are persisted, `None` to keep them in memory only
+    path: Option<PathBuf>,
+    samples: Mutex<Vec<HealthSample>>,
+}
+
+impl HealthHistory {
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            samples: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load the history stored at `path`. Unreadable lines are skipped.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut samples: Vec<HealthSample> = store::load(&path);
+        samples.sort_by_key(|sample| sample.timestamp_ms);
+        Self {
+            path: Some(path),
+            samples: Mutex::new(samples),
+        }
+    }
+
+    /// History in the user's local data directory.
+    pub fn open_default() -> Self {
+        match store::default_path("battery-health.jsonl") {
+            Some(path) => Self::open(path),
+            None => Self::in_memory(),
+        }
+    }
+
+    fn samples(&self) -> MutexGuard<'_, Vec<HealthSample>> {
+        match self.samples.lock() {
+            Ok(samples) => samples,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Record a sample, returning whether it was kept.
+    pub fn record(&self, sample: HealthSample) -> bool {
+        let mut samples = self.samples();
+        if samples
+            .last()
+            .is_some_and(|last| sample.timestamp_ms < last.timestamp_ms + RECORD_INTERVAL_MS)
+        {
+            return false;
+        }
+
+        if let Some(path) = &self.path {
+            store::append(path, &sample);
+        }
+        samples.push(sample);
+        true
+    }
+
+    pub fn forecast(&self) -> HealthForecast {
+        forecast(&self.samples())
+    }
+}
+
+/// Fit trend lines to the samples taken since the battery was last replaced.
+pub fn forecast(samples: &[HealthSample]) -> HealthForecast {
+    let current_battery = samples
+        .windows(2)
+        .rposition(|pair| {
+            pair[1].max_capacity_percentage - pair[0].max_capacity_percentage
+                > REPLACEMENT_JUMP_PERCENTAGE
+        })
+        .map_or(0, |i| i + 1);
+    let samples = &samples[current_battery..];
+
+    let mut forecast = HealthForecast {
+        samples: samples.to_vec(),
+        loss_per_month_percentage: None,
+        loss_per_100_cycles_percentage: None,
+        service_recommended_at_ms: None,
+        replace_soon_at_ms: None,
+    };
+
+    let first = match (samples.first(), samples.last()) {
+        (Some(first), Some(last))
+            if last.timestamp_ms - first.timestamp_ms >= MIN_TREND_SPAN_MS =>
+        {
+            first
+        }
+        _ => return forecast,
+    };
+
+    // Days since the first sample against health
+    let by_day: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|s| {
+            (
+                (s.timestamp_ms - first.timestamp_ms) as f64 / DAY_MS as f64,
+                s.max_capacity_percentage as f64,
+            )
+        })
+        .collect();
+    if let Some((slope, intercept)) = fit_line(&by_day) {
+        forecast.loss_per_month_percentage = Some((-slope * 30.0) as f32);
+        let crossing = |threshold: f32| {
+            if slope >= 0.0 {
+                return None;
+            }
+            let day = (threshold as f64 - intercept) / slope;
+            let ms = first.timestamp_ms as f64 + day * DAY_MS as f64;
+            Some(ms.max(0.0) as u64)
+        };
+        forecast.service_recommended_at_ms = crossing(SERVICE_RECOMMENDED_PERCENTAGE);
+        forecast.replace_soon_at_ms = crossing(REPLACE_SOON_PERCENTAGE);
+    }
+
+    let by_cycle: Vec<(f64, f64)> = samples
+        .iter()
+        .filter_map(|s| {
+            s.cycle_count
+                .map(|cycles| (cycles as f64, s.max_capacity_percentage as f64))
+        })
+        .collect();
+    if let Some((slope, _)) = fit_line(&by_cycle) {
+        forecast.loss_per_100_cycles_percentage = Some((-slope * 100.0) as f32);
+    }
+
+    forecast
+}
+
+#[tauri::command]
+pub fn get_battery_health_forecast(history: State<'_, SharedHealthHistory>) -> HealthForecast {
+    history.forecast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn sample(day: u64, percentage: f32, cycles: u32) -> HealthSample {
+        HealthSample {
+            timestamp_ms: day * DAY_MS,
+            max_capacity_percentage: percentage,
+            cycle_count: Some(cycles),
+        }
+    }
+
+    #[test]
+    fn test_forecast_crossings() {
+        // Losing a point every 10 days, 3 cycles a day, from 90% on day 0
+        let samples: Vec<HealthSample> = (0..=60)
+            .step_by(10)
+            .map(|day| sample(day, 90.0 - day as f32 / 10.0, day as u32 * 3))
+            .collect();
+        let forecast = forecast(&samples);
+
+        assert!((forecast.loss_per_month_percentage.unwrap() - 3.0).abs() < 0.01);
+        assert!((forecast.loss_per_100_cycles_percentage.unwrap() - 3.333).abs() < 0.01);
+        let days = |ms: Option<u64>| (ms.unwrap() as f64 / DAY_MS as f64).round() as u64;
+        assert_eq!(days(forecast.service_recommended_at_ms), 100);
+        assert_eq!(days(forecast.replace_soon_at_ms), 400);
+    }
+
+    #[test]
+    fn test_no_forecast_without_enough_history() {
+        let forecast = forecast(&[sample(0, 90.0, 0), sample(7, 89.0, 20)]);
+        assert_eq!(forecast.samples.len(), 2);
+        assert!(forecast.loss_per_month_percentage.is_none());
+        assert!(forecast.service_recommended_at_ms.is_none());
+    }
+
+    #[test]
+    fn test_no_crossing_when_health_is_flat() {
+        let samples: Vec<HealthSample> = (0..5).map(|week| sample(week * 7, 95.0, 0)).collect();
+        let forecast = forecast(&samples);
+        assert_eq!(forecast.loss_per_month_percentage, Some(0.0));
+        assert!(forecast.service_recommended_at_ms.is_none());
+        // Cycle count never moved
+        assert!(forecast.loss_per_100_cycles_percentage.is_none());
+    }
+
+    #[test]
+    fn test_replacement_restarts_trend() {
+        let samples = vec![
+            sample(0, 72.0, 900),
+            sample(30, 70.0, 950),
+            // New battery
+            sample(40, 100.0, 2),
+            sample(60, 99.0, 40),
+        ];
+        let forecast = forecast(&samples);
+        assert_eq!(forecast.samples.len(), 2);
+        assert_eq!(forecast.samples[0].max_capacity_percentage, 100.0);
+        assert!(forecast.loss_per_month_percentage.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_record_once_a_day_and_persist() {
+        let path = std::env::temp_dir().join(format!(
+            "mac-health-battery-health-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let history = HealthHistory::open(&path);
+        assert!(history.record(sample(1, 90.0, 10)));
+        assert!(!history.record(HealthSample {
+            timestamp_ms: DAY_MS + 60_000,
+            ..sample(1, 89.0, 11)
+        }));
+        assert!(history.record(sample(2, 89.5, 12)));
+
+        let reopened = HealthHistory::open(&path);
+        assert_eq!(reopened.forecast().samples.len(), 2);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,46 @@
+//! JSON Lines files backing the persisted histories.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `name` in the user's local data directory.
+pub fn default_path(name: &str) -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("mac-health").join(name))
+}
+
+/// Read every record in the file, skipping lines that don't parse.
+pub fn load<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn append<T: Serialize>(path: &Path, record: &T) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let (Ok(mut file), Ok(line)) = (
+        OpenOptions::new().create(true).append(true).open(path),
+        serde_json::to_string(record),
+    ) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// Replace the file with `records`.
+pub fn rewrite<T: Serialize>(path: &Path, records: &[T]) {
+    let content: String = records
+        .iter()
+        .filter_map(|record| serde_json::to_string(record).ok())
+        .map(|line| line + "\n")
+        .collect();
+    let _ = fs::write(path, content);
+}
@@ -0,0 +1,374 @@
+//! Keep-awake sessions backed by a `caffeinate` child process.
+//!
+//! `caffeinate` is started with `-w <our pid>`, so it exits along with the
+//! app even when the app is killed before it can stop the session.
+
+use serde::{Deserialize, Serialize};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::State;
+
+use crate::error::MonitorError;
+use crate::sampler::now_ms;
+
+/// Keep-awake state shared between the commands and the tray menu.
+pub type SharedKeepAwake = Arc<KeepAwake>;
+
+/// Event the status is emitted as whenever a session starts or ends.
+pub const CHANGED_EVENT: &str = "keep-awake://changed";
+
+/// How often a session is checked for `caffeinate` having exited on its own.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepAwakeMode {
+    /// Keep the display on
+    Display,
+    /// Keep the system from idle sleeping, the display may still sleep
+    Idle,
+    /// Keep the system awake even with the lid closed, on AC only
+    System,
+}
+
+impl KeepAwakeMode {
+    fn flag(&self) -> &'static str {
+        match self {
+            KeepAwakeMode::Display => "-d",
+            KeepAwakeMode::Idle => "-i",
+            KeepAwakeMode::System => "-s",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeepAwakeStatus {
+    pub active: bool,
+    pub mode: Option<KeepAwakeMode>,
+    pub started_at_ms: Option<u64>,
+    /// `None` while active means until stopped
+    pub ends_at_ms: Option<u64>,
+}
+
+struct Session {
+    id: u64,
+    /// Only reaped once the session is over, so its pid can't be reused by
+    /// another process while it's still ours to kill
+    child: Child,
+    mode: KeepAwakeMode,
+    started_at_ms: u64,
+    ends_at_ms: Option<u64>,
+}
+
+struct Sessions {
+    current: Option<Session>,
+    next_id: u64,
+}
+
+type Listener = Box<dyn Fn(&KeepAwakeStatus) + Send + Sync>;
+
+pub struct KeepAwake {
+    program: String,
+    supported: bool,
+    sessions: Mutex<Sessions>,
+    listener: Mutex<Option<Listener>>,
+}
+
+impl Default for KeepAwake {
+    fn default() -> Self {
+        let mut keep_awake = Self::with_program("caffeinate");
+        // caffeinate only exists on macOS
+        keep_awake.supported = cfg!(target_os = "macos");
+        keep_awake
+    }
+}
+
+impl KeepAwake {
+    fn with_program(program: &str) -> Self {
+        Self {
+            program: program.to_string(),
+            supported: true,
+            sessions: Mutex::new(Sessions {
+                current: None,
+                next_id: 0,
+            }),
+            listener: Mutex::new(None),
+        }
+    }
+
+    fn sessions(&self) -> MutexGuard<'_, Sessions> {
+        match self.sessions.lock() {
+            Ok(sessions) => sessions,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Call `listener` whenever a session starts or ends, including when
+    /// `caffeinate` exits on its own at the end of its duration.
+    pub fn set_listener(&self, listener: impl Fn(&KeepAwakeStatus) + Send + Sync + 'static) {
+        let mut current = match self.listener.lock() {
+            Ok(current) => current,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        *current = Some(Box::new(listener));
+    }
+
+    fn notify(&self, status: &KeepAwakeStatus) {
+        if let Ok(listener) = self.listener.lock() {
+            if let Some(listener) = listener.as_ref() {
+                listener(status);
+            }
+        }
+    }
+
+    /// Report the current status to the listener again, e.g. to undo the
+    /// tray item toggling itself when starting a session failed.
+    pub fn announce(&self) {
+        self.notify(&self.status());
+    }
+
+    pub fn status(&self) -> KeepAwakeStatus {
+        match &self.sessions().current {
+            Some(session) => KeepAwakeStatus {
+                active: true,
+                mode: Some(session.mode),
+                started_at_ms: Some(session.started_at_ms),
+                ends_at_ms: session.ends_at_ms,
+            },
+            None => KeepAwakeStatus {
+                active: false,
+                mode: None,
+                started_at_ms: None,
+                ends_at_ms: None,
+            },
+        }
+    }
+
+    /// Start a session, replacing the current one.
+    pub fn start(
+        self: &Arc<Self>,
+        mode: KeepAwakeMode,
+        duration_minutes: Option<u32>,
+        now_ms: u64,
+    ) -> Result<KeepAwakeStatus, MonitorError> {
+        if !self.supported {
+            return Err(MonitorError::Unsupported("Keep awake".to_string()));
+        }
+        if duration_minutes == Some(0) {
+            return Err(MonitorError::InvalidArgument(
+                "duration_minutes must be at least 1".to_string(),
+            ));
+        }
+
+        // Held throughout, so a concurrent start can't slip its own
+        // caffeinate in between and leave one of the two running unowned
+        let mut sessions = self.sessions();
+        end_session(&mut sessions);
+
+        let child = Command::new(&self.program)
+            .args(caffeinate_args(mode, duration_minutes, std::process::id()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| MonitorError::spawn_failed(&self.program, e))?;
+
+        let id = sessions.next_id;
+        sessions.next_id += 1;
+        sessions.current = Some(Session {
+            id,
+            child,
+            mode,
+            started_at_ms: now_ms,
+            ends_at_ms: duration_minutes.map(|minutes| now_ms + minutes as u64 * 60_000),
+        });
+        drop(sessions);
+
+        // Before the watcher below can report the end of the session
+        let status = self.status();
+        self.notify(&status);
+
+        // Notice when caffeinate exits at the end of its duration
+        let keep_awake = Arc::clone(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(EXIT_POLL_INTERVAL);
+            let mut sessions = keep_awake.sessions();
+            let Some(session) = sessions.current.as_mut().filter(|s| s.id == id) else {
+                // Ended, and reaped, by stop or a newer start
+                return;
+            };
+            if matches!(session.child.try_wait(), Ok(None)) {
+                continue;
+            }
+            sessions.current = None;
+            drop(sessions);
+            keep_awake.notify(&keep_awake.status());
+            return;
+        });
+
+        Ok(status)
+    }
+
+    /// Stop the current session, if any.
+    pub fn stop(&self) -> KeepAwakeStatus {
+        if end_session(&mut self.sessions()) {
+            self.notify(&self.status());
+        }
+        self.status()
+    }
+}
+
+fn end_session(sessions: &mut Sessions) -> bool {
+    match sessions.current.take() {
+        Some(mut session) => {
+            // Fails harmlessly if it already exited on its own
+            let _ = session.child.kill();
+            let _ = session.child.wait();
+            true
+        }
+        None => false,
+    }
+}
+
+fn caffeinate_args(mode: KeepAwakeMode, duration_minutes: Option<u32>, pid: u32) -> Vec<String> {
+    let mut args = vec![mode.flag().to_string(), "-w".to_string(), pid.to_string()];
+    if let Some(minutes) = duration_minutes {
+        args.push("-t".to_string());
+        args.push((minutes as u64 * 60).to_string());
+    }
+    args
+}
+
+#[tauri::command]
+pub fn start_keep_awake(
+    keep_awake: State<'_, SharedKeepAwake>,
+    mode: Option<KeepAwakeMode>,
+    duration_minutes: Option<u32>,
+) -> Result<KeepAwakeStatus, MonitorError> {
+    keep_awake.start(
+        mode.unwrap_or(KeepAwakeMode::Idle),
+        duration_minutes,
+        now_ms(),
+    )
+}
+
+#[tauri::command]
+pub fn stop_keep_awake(keep_awake: State<'_, SharedKeepAwake>) -> KeepAwakeStatus {
+    keep_awake.stop()
+}
+
+#[tauri::command]
+pub fn get_keep_awake_status(keep_awake: State<'_, SharedKeepAwake>) -> KeepAwakeStatus {
+    keep_awake.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_caffeinate_args() {
+        assert_eq!(
+            caffeinate_args(KeepAwakeMode::Display, None, 42),
+            vec!["-d", "-w", "42"]
+        );
+        assert_eq!(
+            caffeinate_args(KeepAwakeMode::System, Some(90), 42),
+            vec!["-s", "-w", "42", "-t", "5400"]
+        );
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        // `true` ignores its arguments and exits at once, like caffeinate
+        // at the end of its duration
+        let keep_awake = Arc::new(KeepAwake::with_program("true"));
+        let (sender, receiver) = mpsc::channel();
+        keep_awake.set_listener(move |status| {
+            let _ = sender.send(status.active);
+        });
+
+        let status = keep_awake
+            .start(KeepAwakeMode::Idle, Some(30), 1_000)
+            .unwrap();
+        assert!(status.active);
+        assert_eq!(status.mode, Some(KeepAwakeMode::Idle));
+        assert_eq!(status.ends_at_ms, Some(1_801_000));
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(true));
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(false));
+        assert!(!keep_awake.status().active);
+        assert!(!keep_awake.stop().active);
+    }
+
+    #[test]
+    fn test_concurrent_starts_leave_one_child() {
+        // Records its pid, then stays up like an open-ended caffeinate
+        let dir =
+            std::env::temp_dir().join(format!("mac-health-keep-awake-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("caffeinate");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho $$ > {}/$$.pid\nexec sleep 30\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let keep_awake = Arc::new(KeepAwake::with_program(script.to_str().unwrap()));
+        let starts: Vec<_> = (0..2)
+            .map(|_| {
+                let keep_awake = Arc::clone(&keep_awake);
+                std::thread::spawn(move || keep_awake.start(KeepAwakeMode::Idle, None, 0))
+            })
+            .collect();
+        for start in starts {
+            assert!(start.join().unwrap().unwrap().active);
+        }
+
+        let running = || {
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .filter_map(|entry| std::fs::read_to_string(entry.unwrap().path()).ok())
+                .filter_map(|pid| pid.trim().parse::<i32>().ok())
+                .filter(|&pid| {
+                    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), None).is_ok()
+                })
+                .count()
+        };
+        // Give the surviving child time to record its pid
+        std::thread::sleep(Duration::from_millis(500));
+        assert_eq!(running(), 1);
+
+        assert!(!keep_awake.stop().active);
+        assert_eq!(running(), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalid_start() {
+        let keep_awake = Arc::new(KeepAwake::with_program("/nonexistent/caffeinate"));
+        let err = keep_awake
+            .start(KeepAwakeMode::Idle, Some(0), 0)
+            .unwrap_err();
+        assert_eq!(err.code(), "InvalidArgument");
+
+        let err = keep_awake.start(KeepAwakeMode::Idle, None, 0).unwrap_err();
+        assert_eq!(err.code(), "CommandFailed");
+        assert!(!keep_awake.status().active);
+
+        if !cfg!(target_os = "macos") {
+            let err = Arc::new(KeepAwake::default())
+                .start(KeepAwakeMode::Idle, None, 0)
+                .unwrap_err();
+            assert_eq!(err.code(), "Unsupported");
+        }
+    }
+}
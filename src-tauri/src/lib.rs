@@ -1,13 +1,27 @@
+mod error;
+mod history;
+mod keep_awake;
 mod monitors;
+mod platform;
+mod sampler;
+mod snapshot;
 
 use serde::Deserialize;
 use tauri::{
+    menu::{CheckMenuItem, Menu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Manager,
+    AppHandle, Emitter, Manager, RunEvent,
 };
 use tauri_plugin_positioner::{Position, WindowExt};
 
-use monitors::{battery, cpu, disk, ram};
+use error::MonitorError;
+use history::battery::{BatteryHistory, SharedBatteryHistory};
+use history::cpu::SharedCpuHistory;
+use history::health::{HealthHistory, SharedHealthHistory};
+use keep_awake::{KeepAwakeMode, SharedKeepAwake};
+use monitors::{battery, cpu, disk, power, ram, sleep};
+use platform::SharedProbe;
+use sampler::SharedSampler;
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -19,14 +33,41 @@ pub enum SystemStatus {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let runner = platform::default_runner();
+
     tauri::Builder::default()
+        .manage(platform::default_probe(runner.clone()))
+        .manage(runner)
+        .manage(SharedSampler::default())
+        .manage(SharedCpuHistory::default())
+        .manage(SharedBatteryHistory::new(BatteryHistory::open_default()))
+        .manage(SharedHealthHistory::new(HealthHistory::open_default()))
+        .manage(SharedKeepAwake::default())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_positioner::init())
         .setup(|app| {
+            // Disabled where there's no caffeinate to run
+            let keep_awake_item = CheckMenuItem::with_id(
+                app,
+                "keep-awake",
+                "Keep Awake",
+                cfg!(target_os = "macos"),
+                false,
+                None::<&str>,
+            )?;
+            let tray_menu = Menu::with_items(app, &[&keep_awake_item])?;
+
             // Build system tray icon
             TrayIconBuilder::with_id("main")
                 .icon(app.default_window_icon().unwrap().clone())
                 .icon_as_template(true) // macOS: adapts to light/dark menu bar
+                .menu(&tray_menu)
+                .show_menu_on_left_click(false)
+                .on_menu_event(|app, event| {
+                    if event.id().as_ref() == "keep-awake" {
+                        toggle_keep_awake(app);
+                    }
+                })
                 .on_tray_icon_event(|tray, event| {
                     // Forward events to positioner for window positioning
                     tauri_plugin_positioner::on_tray_event(tray.app_handle(), &event);
@@ -52,6 +93,24 @@ pub fn run() {
                 })
                 .build(app)?;
 
+            // Keep the tray toggle and the windows in step with the session,
+            // which also ends on its own when its duration runs out
+            let handle = app.handle().clone();
+            app.state::<SharedKeepAwake>().set_listener(move |status| {
+                let _ = keep_awake_item.set_checked(status.active);
+                let _ = handle.emit(keep_awake::CHANGED_EVENT, status);
+            });
+
+            // Push metrics to the windows instead of having them poll
+            sampler::start(
+                app.handle().clone(),
+                app.state::<SharedProbe>().inner().clone(),
+                app.state::<SharedSampler>().inner().clone(),
+                app.state::<SharedCpuHistory>().inner().clone(),
+                app.state::<SharedBatteryHistory>().inner().clone(),
+                app.state::<SharedHealthHistory>().inner().clone(),
+            );
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -67,22 +126,58 @@ pub fn run() {
             cpu::open_activity_monitor,
             // Battery commands
             battery::get_battery_info,
+            battery::get_battery_details,
             battery::open_energy_settings,
+            battery::set_low_power_mode_with_admin,
+            power::get_power_source,
+            sleep::get_sleep_timeline,
+            sleep::get_sleep_assertions,
             // Disk commands
             disk::get_disk_info,
             disk::get_storage_categories,
             disk::refresh_storage_categories,
             disk::open_storage_settings,
             disk::open_system_settings,
+            // Snapshot commands
+            snapshot::get_system_snapshot,
+            // Sampling commands
+            sampler::set_sampling_interval,
+            // History commands
+            history::cpu::get_cpu_history,
+            history::battery::get_battery_history,
+            history::battery::get_battery_sessions,
+            history::health::get_battery_health_forecast,
+            // Keep-awake commands
+            keep_awake::start_keep_awake,
+            keep_awake::stop_keep_awake,
+            keep_awake::get_keep_awake_status,
             // Tray commands
             update_tray_status,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                app.state::<SharedKeepAwake>().stop();
+            }
+        });
+}
+
+/// Start an open-ended idle keep-awake session, or stop the current one.
+fn toggle_keep_awake(app: &AppHandle) {
+    let keep_awake = app.state::<SharedKeepAwake>();
+    if keep_awake.status().active {
+        keep_awake.stop();
+    } else if keep_awake
+        .start(KeepAwakeMode::Idle, None, sampler::now_ms())
+        .is_err()
+    {
+        keep_awake.announce();
+    }
 }
 
 #[tauri::command]
-fn update_tray_status(app: AppHandle, status: SystemStatus) -> Result<(), String> {
+fn update_tray_status(app: AppHandle, status: SystemStatus) -> Result<(), MonitorError> {
     // For now, we just acknowledge the status change.
     // Custom icons (tray-normal.png, tray-warning.png, tray-critical.png)
     // can be added to the icons/ folder later.
@@ -96,7 +191,7 @@ fn update_tray_status(app: AppHandle, status: SystemStatus) -> Result<(), String
 
     // Verify the tray exists (for future icon updates)
     if app.tray_by_id("main").is_none() {
-        return Err("Tray icon not found".to_string());
+        return Err(MonitorError::NotFound("Tray icon".to_string()));
     }
 
     // TODO: When custom icons are added, load and set the appropriate icon here
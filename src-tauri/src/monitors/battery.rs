@@ -1,8 +1,23 @@
-use serde::Serialize;
-use std::process::Command;
-use tauri::AppHandle;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
 use tauri_plugin_shell::ShellExt;
 
+use crate::error::MonitorError;
+use crate::history::battery::SharedBatteryHistory;
+use crate::platform::{
+    run_with_admin, BatteryReading, CommandRunner, SharedProbe, SharedRunner, SystemProbe,
+};
+use crate::sampler::now_ms;
+
+/// Health below which the condition is "Service Recommended".
+pub const SERVICE_RECOMMENDED_PERCENTAGE: f32 = 80.0;
+
+/// Health below which the condition is "Replace Soon".
+pub const REPLACE_SOON_PERCENTAGE: f32 = 50.0;
+
+/// Discharge current, while connected, that still counts as gauge noise.
+const DRAIN_NOISE_MA: f32 = 100.0;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct BatteryInfo {
     pub percentage: f32,
@@ -16,109 +31,124 @@ pub struct BatteryInfo {
     pub time_to_empty_minutes: Option<u32>,
     pub temperature_celsius: Option<f32>,
     pub voltage_volts: Option<f32>,
+    /// Current through the battery, negative while discharging
+    pub amperage_ma: Option<f32>,
+    pub power_draw_watts: Option<f32>,
+    pub cell_voltages_mv: Vec<u32>,
+    /// Spread between the highest and lowest cell voltage
+    pub cell_imbalance_mv: Option<u32>,
+    /// The connected power adapter, if any
+    pub adapter: Option<AdapterInfo>,
+    /// Estimate from our own samples, for when macOS reports no time
+    pub time_estimate: Option<TimeEstimate>,
+    /// `None` when the platform has no Low Power Mode
+    pub low_power_mode: Option<bool>,
 }
 
-fn get_ioreg_value(output: &str, key: &str) -> Option<String> {
-    // Look for top-level key = value pattern (with spaces around =)
-    // This avoids matching nested values inside BatteryData
-    let pattern = format!("{} = ", key);
-    for line in output.lines() {
-        if let Some(pos) = line.find(&pattern) {
-            let value_start = pos + pattern.len();
-            let value = line[value_start..].trim().trim_matches('"');
-            // Handle values that might have trailing content
-            let value = value.split_whitespace().next().unwrap_or(value);
-            return Some(value.to_string());
-        }
-    }
-    None
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimeEstimate {
+    /// To full while charging, to empty otherwise
+    pub minutes: u32,
+    pub is_charging: bool,
+    pub confidence: EstimateConfidence,
+    /// Percentage points per hour, negative while discharging
+    pub rate_percentage_per_hour: f32,
 }
 
-#[tauri::command]
-pub fn get_battery_info() -> Result<BatteryInfo, String> {
-    // Use ioreg to get accurate battery info from macOS
-    let output = Command::new("ioreg")
-        .args(["-rc", "AppleSmartBattery"])
-        .output()
-        .map_err(|e| format!("Failed to run ioreg: {}", e))?;
-
-    if !output.status.success() {
-        return Err("ioreg command failed".to_string());
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    if stdout.trim().is_empty() {
-        return Err("No battery found (desktop Mac?)".to_string());
-    }
-
-    // Parse values from ioreg output
-    // Use AppleRaw* values for accurate readings (the non-raw ones are percentages)
-    let current_capacity = get_ioreg_value(&stdout, "\"AppleRawCurrentCapacity\"")
-        .and_then(|v| v.parse::<f32>().ok())
-        .unwrap_or(0.0);
-
-    let max_capacity = get_ioreg_value(&stdout, "\"AppleRawMaxCapacity\"")
-        .and_then(|v| v.parse::<f32>().ok())
-        .unwrap_or(100.0);
-
-    let design_capacity = get_ioreg_value(&stdout, "\"DesignCapacity\"")
-        .and_then(|v| v.parse::<f32>().ok())
-        .unwrap_or(100.0);
-
-    let is_charging = get_ioreg_value(&stdout, "\"IsCharging\"")
-        .map(|v| v == "Yes" || v == "1")
-        .unwrap_or(false);
-
-    let external_connected = get_ioreg_value(&stdout, "\"ExternalConnected\"")
-        .map(|v| v == "Yes" || v == "1")
-        .unwrap_or(false);
-
-    let cycle_count =
-        get_ioreg_value(&stdout, "\"CycleCount\"").and_then(|v| v.parse::<u32>().ok());
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EstimateConfidence {
+    /// Measured over the last few minutes
+    High,
+    /// Taken from the previous session, right after plugging or unplugging
+    Low,
+}
 
-    let temperature = get_ioreg_value(&stdout, "\"Temperature\"")
-        .and_then(|v| v.parse::<f32>().ok())
-        .map(|t| t / 100.0); // Temperature is in centi-degrees
+#[derive(Debug, Clone, Serialize)]
+pub struct AdapterInfo {
+    pub watts: Option<u32>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// IOKit family code in hex, e.g. `0xe000400a` for USB-C PD
+    pub family: Option<String>,
+    pub voltage_volts: Option<f32>,
+    pub current_amps: Option<f32>,
+    pub is_wireless: bool,
+    /// The adapter cannot keep up with what the machine draws
+    pub is_underpowered: bool,
+}
 
-    let voltage = get_ioreg_value(&stdout, "\"Voltage\"")
-        .and_then(|v| v.parse::<f32>().ok())
-        .map(|v| v / 1000.0); // Voltage is in mV
+#[tauri::command]
+pub fn get_battery_info(
+    probe: State<'_, SharedProbe>,
+    history: State<'_, SharedBatteryHistory>,
+) -> Result<BatteryInfo, MonitorError> {
+    let mut info = battery_info(&**probe)?;
+    info.time_estimate = history.estimate_time_remaining(now_ms(), &info);
+    Ok(info)
+}
 
-    let time_to_empty = get_ioreg_value(&stdout, "\"TimeRemaining\"")
-        .and_then(|v| v.parse::<u32>().ok())
-        .filter(|&v| v < 65535); // Filter out invalid values
+pub fn battery_info(probe: &dyn SystemProbe) -> Result<BatteryInfo, MonitorError> {
+    let reading = probe.battery()?.ok_or(MonitorError::NoBattery)?;
 
-    let time_to_full = if is_charging {
-        get_ioreg_value(&stdout, "\"TimeRemaining\"")
-            .and_then(|v| v.parse::<u32>().ok())
-            .filter(|&v| v < 65535)
-    } else {
-        None
-    };
+    let is_charging = reading.is_charging;
+    let external_connected = reading.external_connected;
 
     // Calculate percentages
-    let percentage = if max_capacity > 0.0 {
-        (current_capacity / max_capacity) * 100.0
+    let percentage = if reading.max_capacity > 0.0 {
+        (reading.current_capacity / reading.max_capacity) * 100.0
     } else {
         0.0
     };
 
-    let max_capacity_percentage = if design_capacity > 0.0 {
-        (max_capacity / design_capacity) * 100.0
+    let max_capacity_percentage = if reading.design_capacity > 0.0 {
+        (reading.max_capacity / reading.design_capacity) * 100.0
     } else {
         100.0
     };
 
     // Determine condition based on health
-    let condition = if max_capacity_percentage >= 80.0 {
+    let condition = if max_capacity_percentage >= SERVICE_RECOMMENDED_PERCENTAGE {
         "Normal".to_string()
-    } else if max_capacity_percentage >= 50.0 {
+    } else if max_capacity_percentage >= REPLACE_SOON_PERCENTAGE {
         "Service Recommended".to_string()
     } else {
         "Replace Soon".to_string()
     };
 
+    // Without a system-wide measurement, the draw is only known while the
+    // battery alone powers the machine
+    let power_draw_watts =
+        reading
+            .system_power_watts
+            .or_else(|| match (reading.voltage_volts, reading.amperage_ma) {
+                (Some(volts), Some(ma)) if !external_connected && ma < 0.0 => {
+                    Some(volts * -ma / 1000.0)
+                }
+                _ => None,
+            });
+
+    let cell_imbalance_mv = match (
+        reading.cell_voltages_mv.iter().max(),
+        reading.cell_voltages_mv.iter().min(),
+    ) {
+        (Some(max), Some(min)) if reading.cell_voltages_mv.len() > 1 => Some(max - min),
+        _ => None,
+    };
+
+    let adapter = reading.adapter.as_ref().map(|adapter| AdapterInfo {
+        watts: adapter.watts,
+        name: adapter.name.clone(),
+        description: adapter.description.clone(),
+        family: adapter
+            .family_code
+            .map(|code| format!("{:#x}", code as u32)),
+        voltage_volts: adapter.voltage_mv.map(|mv| mv as f32 / 1000.0),
+        current_amps: adapter.current_ma.map(|ma| ma as f32 / 1000.0),
+        is_wireless: adapter.is_wireless,
+        is_underpowered: is_underpowered(&reading, adapter.watts),
+    });
+
     let power_source = if external_connected {
         "AC Adapter".to_string()
     } else {
@@ -132,32 +162,166 @@ pub fn get_battery_info() -> Result<BatteryInfo, String> {
         power_source,
         condition,
         max_capacity_percentage,
-        cycle_count,
-        time_to_full_minutes: if is_charging { time_to_full } else { None },
-        time_to_empty_minutes: if !is_charging { time_to_empty } else { None },
-        temperature_celsius: temperature,
-        voltage_volts: voltage,
+        cycle_count: reading.cycle_count,
+        time_to_full_minutes: if is_charging {
+            reading
+                .time_to_full_minutes
+                .or(reading.time_remaining_minutes)
+        } else {
+            None
+        },
+        time_to_empty_minutes: if !is_charging {
+            reading
+                .time_to_empty_minutes
+                .or(reading.time_remaining_minutes)
+        } else {
+            None
+        },
+        temperature_celsius: reading.temperature_celsius,
+        voltage_volts: reading.voltage_volts,
+        amperage_ma: reading.amperage_ma,
+        power_draw_watts,
+        cell_voltages_mv: reading.cell_voltages_mv,
+        cell_imbalance_mv,
+        adapter,
+        time_estimate: None,
+        low_power_mode: probe.low_power_mode(),
+    })
+}
+
+/// An adapter is underpowered when the battery drains while it is plugged
+/// in, or when the system draws more than the adapter is rated for.
+fn is_underpowered(reading: &BatteryReading, watts: Option<u32>) -> bool {
+    let draining =
+        !reading.is_charging && reading.amperage_ma.is_some_and(|ma| ma < -DRAIN_NOISE_MA);
+    let overloaded = match (watts, reading.system_power_watts) {
+        (Some(watts), Some(load)) => load > watts as f32,
+        _ => false,
+    };
+    draining || overloaded
+}
+
+/// Which power source a setting applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingSource {
+    Battery,
+    Ac,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LowPowerModeResult {
+    pub success: bool,
+    pub message: String,
+}
+
+#[tauri::command(async)]
+pub fn set_low_power_mode_with_admin(
+    runner: State<'_, SharedRunner>,
+    source: SettingSource,
+    enabled: bool,
+) -> Result<LowPowerModeResult, MonitorError> {
+    // pmset and the osascript privilege prompt only exist on macOS
+    if !cfg!(target_os = "macos") {
+        return Err(MonitorError::Unsupported("Low Power Mode".to_string()));
+    }
+    set_low_power_mode(&**runner, source, enabled)
+}
+
+pub fn set_low_power_mode(
+    runner: &dyn CommandRunner,
+    source: SettingSource,
+    enabled: bool,
+) -> Result<LowPowerModeResult, MonitorError> {
+    let (flag, source_name) = match source {
+        SettingSource::Battery => ("-b", "battery"),
+        SettingSource::Ac => ("-c", "power adapter"),
+    };
+    let command = format!("pmset {} lowpowermode {}", flag, u8::from(enabled));
+
+    // Prompts the user for their password
+    if !run_with_admin(runner, &command)? {
+        return Ok(LowPowerModeResult {
+            success: false,
+            message: "Authentication cancelled".to_string(),
+        });
+    }
+
+    Ok(LowPowerModeResult {
+        success: true,
+        message: format!(
+            "Low Power Mode {} on {}",
+            if enabled { "enabled" } else { "disabled" },
+            source_name
+        ),
+    })
+}
+
+/// Identity and age of the battery, as needed for warranty claims.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatteryDetails {
+    pub serial: Option<String>,
+    pub manufacturer: Option<String>,
+    pub device_name: Option<String>,
+    /// `YYYY-MM-DD`
+    pub manufacture_date: Option<String>,
+    pub age_days: Option<u32>,
+    pub design_capacity_mah: Option<u32>,
+    pub full_charge_capacity_mah: Option<u32>,
+    pub cycle_count: Option<u32>,
+}
+
+#[tauri::command]
+pub fn get_battery_details(probe: State<'_, SharedProbe>) -> Result<BatteryDetails, MonitorError> {
+    battery_details(&**probe, now_ms())
+}
+
+pub fn battery_details(
+    probe: &dyn SystemProbe,
+    now_ms: u64,
+) -> Result<BatteryDetails, MonitorError> {
+    let reading = probe.battery()?.ok_or(MonitorError::NoBattery)?;
+    let identity = reading.identity;
+    let today = (now_ms / (24 * 60 * 60 * 1000)) as i64;
+
+    Ok(BatteryDetails {
+        serial: identity.serial,
+        manufacturer: identity.manufacturer,
+        device_name: identity.device_name,
+        manufacture_date: identity
+            .manufacture_date
+            .map(|date| format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)),
+        age_days: identity
+            .manufacture_date
+            .map(|date| (today - date.days_since_epoch()).max(0) as u32),
+        design_capacity_mah: identity.design_capacity_mah,
+        full_charge_capacity_mah: identity.full_charge_capacity_mah,
+        cycle_count: reading.cycle_count,
     })
 }
 
 #[tauri::command]
 #[allow(deprecated)]
-pub async fn open_energy_settings(app: AppHandle) -> Result<(), String> {
+pub async fn open_energy_settings(app: AppHandle) -> Result<(), MonitorError> {
     app.shell()
         .open(
             "x-apple.systempreferences:com.apple.preference.battery",
             None,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| MonitorError::Internal(e.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::platform::testing::{replay, FakeProbe};
+    use crate::platform::{
+        default_probe, default_runner, AdapterReading, BatteryIdentity, ManufactureDate,
+    };
 
     #[test]
     fn test_get_battery_info() {
-        let result = get_battery_info();
+        let result = battery_info(&*default_probe(default_runner()));
         // Battery info may not be available on desktop Macs
         match result {
             Ok(info) => {
@@ -170,8 +334,173 @@ mod tests {
             }
             Err(e) => {
                 // Expected on desktop Macs without batteries
-                assert!(e.contains("No battery found") || e.contains("Failed"));
+                assert!(matches!(
+                    e,
+                    MonitorError::NoBattery | MonitorError::CommandFailed { .. }
+                ));
             }
         }
     }
+
+    #[test]
+    fn test_battery_info_from_reading() {
+        let probe = FakeProbe {
+            battery: Some(BatteryReading {
+                current_capacity: 2000.0,
+                max_capacity: 4000.0,
+                design_capacity: 5000.0,
+                is_charging: false,
+                external_connected: false,
+                time_remaining_minutes: Some(95),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let info = battery_info(&probe).unwrap();
+        assert_eq!(info.percentage, 50.0);
+        assert_eq!(info.max_capacity_percentage, 80.0);
+        assert_eq!(info.condition, "Normal");
+        assert_eq!(info.power_source, "Battery");
+        assert_eq!(info.time_to_empty_minutes, Some(95));
+        assert_eq!(info.time_to_full_minutes, None);
+    }
+
+    #[test]
+    fn test_battery_details() {
+        let probe = FakeProbe {
+            battery: Some(BatteryReading {
+                cycle_count: Some(150),
+                identity: BatteryIdentity {
+                    serial: Some("F8Y2147A1UPQ1CWAH".to_string()),
+                    manufacture_date: ManufactureDate::new(2023, 1, 21),
+                    design_capacity_mah: Some(4563),
+                    full_charge_capacity_mah: Some(4382),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // 2024-01-21T12:00:00Z
+        let details = battery_details(&probe, 1_705_838_400_000).unwrap();
+        assert_eq!(details.serial.as_deref(), Some("F8Y2147A1UPQ1CWAH"));
+        assert_eq!(details.manufacture_date.as_deref(), Some("2023-01-21"));
+        assert_eq!(details.age_days, Some(365));
+        assert_eq!(details.design_capacity_mah, Some(4563));
+        assert_eq!(details.cycle_count, Some(150));
+
+        let err = battery_details(&FakeProbe::default(), 0).unwrap_err();
+        assert_eq!(err.code(), "NoBattery");
+    }
+
+    #[test]
+    fn test_set_low_power_mode() {
+        let result = set_low_power_mode(&replay("charging"), SettingSource::Ac, true).unwrap();
+        assert!(result.success);
+        assert_eq!(result.message, "Low Power Mode enabled on power adapter");
+
+        let result = set_low_power_mode(&replay("macbook"), SettingSource::Battery, false).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.message, "Authentication cancelled");
+    }
+
+    #[test]
+    fn test_power_draw_and_cell_imbalance() {
+        let mut reading = BatteryReading {
+            current_capacity: 3000.0,
+            max_capacity: 4000.0,
+            design_capacity: 4000.0,
+            voltage_volts: Some(12.0),
+            amperage_ma: Some(-1500.0),
+            cell_voltages_mv: vec![4012, 3987, 4005],
+            ..Default::default()
+        };
+        let probe = FakeProbe {
+            battery: Some(reading.clone()),
+            ..Default::default()
+        };
+        let info = battery_info(&probe).unwrap();
+        assert_eq!(info.power_draw_watts, Some(18.0));
+        assert_eq!(info.amperage_ma, Some(-1500.0));
+        assert_eq!(info.cell_imbalance_mv, Some(25));
+
+        // On AC the battery current says nothing about the system draw
+        reading.external_connected = true;
+        reading.amperage_ma = Some(800.0);
+        reading.cell_voltages_mv = vec![4012];
+        let probe = FakeProbe {
+            battery: Some(reading.clone()),
+            ..Default::default()
+        };
+        let info = battery_info(&probe).unwrap();
+        assert_eq!(info.power_draw_watts, None);
+        assert_eq!(info.cell_imbalance_mv, None);
+
+        // Unless the platform measures it
+        reading.system_power_watts = Some(23.5);
+        let probe = FakeProbe {
+            battery: Some(reading),
+            ..Default::default()
+        };
+        assert_eq!(battery_info(&probe).unwrap().power_draw_watts, Some(23.5));
+    }
+
+    #[test]
+    fn test_underpowered_adapter() {
+        let adapter = AdapterReading {
+            watts: Some(96),
+            family_code: Some(-536854518),
+            voltage_mv: Some(20000),
+            current_ma: Some(4700),
+            ..Default::default()
+        };
+        let mut reading = BatteryReading {
+            max_capacity: 100.0,
+            design_capacity: 100.0,
+            external_connected: true,
+            is_charging: true,
+            amperage_ma: Some(2100.0),
+            system_power_watts: Some(35.0),
+            adapter: Some(adapter),
+            ..Default::default()
+        };
+
+        let info = battery_info(&FakeProbe {
+            battery: Some(reading.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        let adapter = info.adapter.unwrap();
+        assert_eq!(adapter.family.as_deref(), Some("0xe000400a"));
+        assert_eq!(adapter.voltage_volts, Some(20.0));
+        assert_eq!(adapter.current_amps, Some(4.7));
+        assert!(!adapter.is_underpowered);
+
+        // Plugged in but the battery still drains
+        reading.is_charging = false;
+        reading.amperage_ma = Some(-900.0);
+        let info = battery_info(&FakeProbe {
+            battery: Some(reading.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(info.adapter.unwrap().is_underpowered);
+
+        // Held at a charge limit: no current either way
+        reading.amperage_ma = Some(-20.0);
+        let info = battery_info(&FakeProbe {
+            battery: Some(reading),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!info.adapter.unwrap().is_underpowered);
+    }
+
+    #[test]
+    fn test_battery_info_without_battery() {
+        let err = battery_info(&FakeProbe::default()).unwrap_err();
+        assert_eq!(err, MonitorError::NoBattery);
+    }
 }
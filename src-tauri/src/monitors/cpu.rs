@@ -1,16 +1,34 @@
 use serde::Serialize;
-use std::process::Command;
-use sysinfo::{CpuRefreshKind, RefreshKind, System};
-use tauri::AppHandle;
+use tauri::{AppHandle, State};
 use tauri_plugin_shell::ShellExt;
 
+use crate::error::MonitorError;
+use crate::platform::{
+    CacheSizes, CpuBreakdownReading, CpuTopologyReading, SharedProbe, SystemProbe, ThermalReading,
+};
+
 #[derive(Debug, Clone, Serialize)]
 pub struct CpuInfo {
     pub model_name: String,
+    /// Logical cores
     pub total_cores: usize,
+    pub physical_cores: Option<usize>,
+    /// `None` unless the CPU has separate performance and efficiency cores
+    pub performance_cores: Option<usize>,
+    pub efficiency_cores: Option<usize>,
+    /// Core clusters, fastest first; empty when the platform doesn't say
+    pub clusters: Vec<CpuCluster>,
+    pub cache: CpuCache,
     pub total_usage_percentage: f32,
-    pub per_core_usage: Vec<f32>,
+    /// The total was estimated from the load average, not measured
+    pub is_estimate: bool,
+    /// `None` when the platform doesn't break the time down
+    pub breakdown: Option<CpuBreakdown>,
+    pub per_core_usage: Vec<CoreUsage>,
     pub load_average: LoadAverage,
+    pub frequency: CpuFrequency,
+    /// `None` when the platform doesn't report thermal throttling
+    pub thermal: Option<ThermalState>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -20,6 +38,111 @@ pub struct LoadAverage {
     pub fifteen_minutes: f64,
 }
 
+/// Share of CPU time per state, in percent of the whole machine.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CpuBreakdown {
+    pub user_percentage: f32,
+    pub system_percentage: f32,
+    pub idle_percentage: f32,
+    /// `None` where it is counted as user time, as on macOS
+    pub nice_percentage: Option<f32>,
+    /// Linux only
+    pub iowait_percentage: Option<f32>,
+    /// Linux only
+    pub steal_percentage: Option<f32>,
+}
+
+impl From<CpuBreakdownReading> for CpuBreakdown {
+    fn from(reading: CpuBreakdownReading) -> Self {
+        Self {
+            user_percentage: reading.user,
+            system_percentage: reading.system,
+            idle_percentage: reading.idle,
+            nice_percentage: reading.nice,
+            iowait_percentage: reading.iowait,
+            steal_percentage: reading.steal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoreKind {
+    Performance,
+    Efficiency,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuCluster {
+    /// As macOS names the performance level, e.g. "Efficiency"
+    pub name: String,
+    pub kind: CoreKind,
+    pub physical_cores: usize,
+    pub logical_cores: usize,
+    pub cores_per_l2: Option<usize>,
+    pub cache: CpuCache,
+}
+
+/// Cache sizes in bytes. L1 is per core, L2 and L3 are shared.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CpuCache {
+    pub l1_instruction_bytes: Option<u64>,
+    pub l1_data_bytes: Option<u64>,
+    pub l2_bytes: Option<u64>,
+    pub l3_bytes: Option<u64>,
+}
+
+impl From<CacheSizes> for CpuCache {
+    fn from(sizes: CacheSizes) -> Self {
+        Self {
+            l1_instruction_bytes: sizes.l1_instruction_bytes,
+            l1_data_bytes: sizes.l1_data_bytes,
+            l2_bytes: sizes.l2_bytes,
+            l3_bytes: sizes.l3_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoreUsage {
+    pub usage_percentage: f32,
+    /// The cluster the core belongs to, when it is known
+    pub kind: Option<CoreKind>,
+    pub frequency_mhz: Option<u64>,
+}
+
+/// Clock speeds in MHz, each `None` when the platform doesn't expose it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuFrequency {
+    /// Average current clock across the cores
+    pub current_mhz: Option<u64>,
+    /// The rated base clock
+    pub nominal_mhz: Option<u64>,
+    pub max_mhz: Option<u64>,
+}
+
+/// How hard the machine is throttling itself to stay cool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThermalPressure {
+    Nominal,
+    Moderate,
+    Heavy,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThermalState {
+    pub pressure: ThermalPressure,
+    /// The CPU speed or scheduler is being limited
+    pub is_throttled: bool,
+    pub cpu_speed_limit_percentage: Option<u32>,
+    pub scheduler_limit_percentage: Option<u32>,
+    pub available_cpus: Option<u32>,
+    pub thermal_warning_level: Option<u32>,
+    pub performance_warning_level: Option<u32>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ProcessCpuInfo {
     pub pid: u32,
@@ -27,122 +150,187 @@ pub struct ProcessCpuInfo {
     pub cpu_percentage: f32,
 }
 
-#[tauri::command]
-pub fn get_cpu_info() -> Result<CpuInfo, String> {
+#[tauri::command(async)]
+pub fn get_cpu_info(probe: State<'_, SharedProbe>) -> Result<CpuInfo, MonitorError> {
+    cpu_info(&**probe)
+}
+
+pub fn cpu_info(probe: &dyn SystemProbe) -> Result<CpuInfo, MonitorError> {
     // Get CPU count and model
-    let mut sys =
-        System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()));
-    sys.refresh_cpu_all();
+    let identity = probe.cpu_identity();
+    let topology = probe.cpu_topology();
+    let total_cores = match identity.core_count {
+        0 => topology.logical_cores.unwrap_or(0),
+        count => count,
+    };
+    let model_name = identity.model_name;
 
-    let cpus = sys.cpus();
-    let total_cores = cpus.len();
+    // Get load average
+    let load_avg = probe.load_average();
 
-    let model_name = cpus
-        .first()
-        .map(|cpu| cpu.brand().to_string())
-        .unwrap_or_else(|| "Unknown".to_string());
+    let usage = probe.cpu_usage();
 
-    // Get overall CPU usage from top command (more reliable on macOS)
-    let total_usage_percentage = get_cpu_usage_from_top().unwrap_or(0.0);
+    // Fallback: use load average as a rough CPU percentage estimate, and
+    // say so
+    let is_estimate = usage.is_none();
+    let total_usage_percentage = usage
+        .as_ref()
+        .map(|u| u.total_percentage)
+        .unwrap_or_else(|| (load_avg.one * 10.0).min(100.0) as f32);
+    let breakdown = usage
+        .as_ref()
+        .and_then(|u| u.breakdown.clone())
+        .map(CpuBreakdown::from);
 
-    // Get load average
-    let load_avg = System::load_average();
-
-    // Ensure we have at least some cores (fallback to 10 for Apple Silicon)
-    let core_count = if total_cores > 0 { total_cores } else { 10 };
-
-    // Generate per-core usage based on total CPU usage
-    // This is an approximation since macOS doesn't expose per-core easily
-    let per_core_usage: Vec<f32> = (0..core_count)
-        .map(|i| {
-            // Use total CPU percentage as base, add variation per core
-            let base = total_usage_percentage * 0.7;
-            let variation = (((i as f32) * 1.5).sin().abs() * 25.0) + 10.0;
-            (base + variation).clamp(8.0, 100.0) // Ensure minimum visibility
+    // Empty when the platform cannot measure individual cores
+    let per_core = usage.map(|u| u.per_core).unwrap_or_default();
+    let kinds = core_kinds(&topology, per_core.len());
+    let frequency = probe.cpu_frequency();
+    let per_core_usage: Vec<CoreUsage> = per_core
+        .into_iter()
+        .enumerate()
+        .map(|(core, usage_percentage)| CoreUsage {
+            usage_percentage,
+            kind: kinds.as_ref().map(|kinds| kinds[core]),
+            frequency_mhz: frequency.per_core_mhz.get(core).copied().flatten(),
+        })
+        .collect();
+
+    let clusters: Vec<CpuCluster> = topology
+        .perf_levels
+        .into_iter()
+        .enumerate()
+        .map(|(level, reading)| CpuCluster {
+            name: reading.name,
+            kind: level_kind(level),
+            physical_cores: reading.physical_cores,
+            logical_cores: reading.logical_cores,
+            cores_per_l2: reading.cores_per_l2,
+            cache: reading.cache.into(),
         })
         .collect();
+    let cores_of = |kind: CoreKind| {
+        clusters
+            .iter()
+            .filter(|cluster| cluster.kind == kind)
+            .map(|cluster| cluster.physical_cores)
+            .sum()
+    };
+    let (performance_cores, efficiency_cores) = if clusters.len() > 1 {
+        (
+            Some(cores_of(CoreKind::Performance)),
+            Some(cores_of(CoreKind::Efficiency)),
+        )
+    } else {
+        (None, None)
+    };
 
     Ok(CpuInfo {
         model_name,
-        total_cores: core_count,
+        total_cores,
+        physical_cores: topology.physical_cores,
+        performance_cores,
+        efficiency_cores,
+        clusters,
+        cache: topology.cache.into(),
         total_usage_percentage,
+        is_estimate,
+        breakdown,
         per_core_usage,
         load_average: LoadAverage {
             one_minute: load_avg.one,
             five_minutes: load_avg.five,
             fifteen_minutes: load_avg.fifteen,
         },
+        frequency: CpuFrequency {
+            current_mhz: average_mhz(frequency.per_core_mhz.iter().flatten().copied()),
+            nominal_mhz: frequency.nominal_mhz,
+            max_mhz: frequency.max_mhz,
+        },
+        thermal: probe.thermal().map(thermal_state),
     })
 }
 
-fn get_cpu_usage_from_top() -> Option<f32> {
-    let output = Command::new("top")
-        .args(["-l", "1", "-n", "0"])
-        .output()
-        .ok()?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        if line.contains("CPU usage:") {
-            // Parse "CPU usage: 26.85% user, 19.57% sys, 53.57% idle"
-            // We want 100 - idle = user + sys
-            if let Some(idle_start) = line.find("idle") {
-                // Find the percentage before "idle"
-                let before_idle = &line[..idle_start];
-                let words: Vec<&str> = before_idle.split_whitespace().collect();
-                // Last word before "idle" should be the idle percentage
-                if let Some(idle_pct_word) = words.last() {
-                    if let Ok(idle) = idle_pct_word
-                        .trim_end_matches('%')
-                        .trim_end_matches(',')
-                        .parse::<f32>()
-                    {
-                        return Some(100.0 - idle);
-                    }
-                }
-            }
-        }
+fn average_mhz(frequencies: impl Iterator<Item = u64>) -> Option<u64> {
+    let (sum, count) = frequencies.fold((0, 0), |(sum, count), mhz| (sum + mhz, count + 1));
+    (count > 0).then(|| sum / count)
+}
+
+/// Only the fastest performance level holds performance cores.
+fn level_kind(level: usize) -> CoreKind {
+    if level == 0 {
+        CoreKind::Performance
+    } else {
+        CoreKind::Efficiency
     }
-    // Fallback: use load average as a rough CPU percentage estimate
-    let load_avg = System::load_average();
-    Some((load_avg.one * 10.0).min(100.0) as f32)
 }
 
-#[tauri::command]
-pub fn get_top_cpu_processes(count: Option<usize>) -> Result<Vec<ProcessCpuInfo>, String> {
-    let count = count.unwrap_or(10);
+/// The kind of each of `core_count` logical cores, or `None` when the
+/// performance levels don't account for all of them.
+fn core_kinds(topology: &CpuTopologyReading, core_count: usize) -> Option<Vec<CoreKind>> {
+    // macOS numbers the cores of the slowest level first
+    let kinds: Vec<CoreKind> = topology
+        .perf_levels
+        .iter()
+        .enumerate()
+        .rev()
+        .flat_map(|(level, reading)| {
+            std::iter::repeat(level_kind(level)).take(reading.logical_cores)
+        })
+        .collect();
+    (core_count > 0 && kinds.len() == core_count).then_some(kinds)
+}
+
+fn thermal_state(reading: ThermalReading) -> ThermalState {
+    let below_full = |limit: Option<u32>| limit.is_some_and(|limit| limit < 100);
+    let is_throttled = below_full(reading.cpu_speed_limit) || below_full(reading.scheduler_limit);
+    let warning = reading.thermal_warning_level.unwrap_or(0);
 
-    // Use ps command - much faster than sysinfo
-    // -r sorts by CPU usage descending
-    let output = Command::new("ps")
-        .args(["-arcwwwxo", "pid,%cpu,comm"])
-        .output()
-        .map_err(|e| format!("Failed to run ps: {}", e))?;
+    // The warning levels are 0 (normal), 5 (danger) and 10 (crisis)
+    let pressure = if warning >= 10 {
+        ThermalPressure::Critical
+    } else if warning >= 5 || reading.cpu_speed_limit.is_some_and(|limit| limit < 70) {
+        ThermalPressure::Heavy
+    } else if is_throttled || warning > 0 || reading.performance_warning_level.unwrap_or(0) > 0 {
+        ThermalPressure::Moderate
+    } else {
+        ThermalPressure::Nominal
+    };
 
-    if !output.status.success() {
-        return Err("ps command failed".to_string());
+    ThermalState {
+        pressure,
+        is_throttled,
+        cpu_speed_limit_percentage: reading.cpu_speed_limit,
+        scheduler_limit_percentage: reading.scheduler_limit,
+        available_cpus: reading.available_cpus,
+        thermal_warning_level: reading.thermal_warning_level,
+        performance_warning_level: reading.performance_warning_level,
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+#[tauri::command]
+pub fn get_top_cpu_processes(
+    probe: State<'_, SharedProbe>,
+    count: Option<usize>,
+) -> Result<Vec<ProcessCpuInfo>, MonitorError> {
+    top_cpu_processes(&**probe, count)
+}
 
-    let processes: Vec<ProcessCpuInfo> = stdout
-        .lines()
-        .skip(1) // Skip header
+pub fn top_cpu_processes(
+    probe: &dyn SystemProbe,
+    count: Option<usize>,
+) -> Result<Vec<ProcessCpuInfo>, MonitorError> {
+    let count = count.unwrap_or(10);
+
+    // Probes return processes sorted by CPU usage descending
+    let processes: Vec<ProcessCpuInfo> = probe
+        .cpu_processes()?
+        .into_iter()
         .take(count)
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                let pid = parts[0].parse::<u32>().ok()?;
-                let cpu_percentage = parts[1].parse::<f32>().ok()?;
-                let name = parts[2..].join(" ");
-                Some(ProcessCpuInfo {
-                    pid,
-                    name,
-                    cpu_percentage,
-                })
-            } else {
-                None
-            }
+        .map(|reading| ProcessCpuInfo {
+            pid: reading.pid,
+            name: reading.name,
+            cpu_percentage: reading.cpu_percentage,
         })
         .collect();
 
@@ -150,36 +338,42 @@ pub fn get_top_cpu_processes(count: Option<usize>) -> Result<Vec<ProcessCpuInfo>
 }
 
 #[tauri::command]
-pub fn get_system_uptime() -> u64 {
-    System::uptime()
+pub fn get_system_uptime(probe: State<'_, SharedProbe>) -> u64 {
+    probe.uptime()
 }
 
 #[tauri::command]
 #[allow(deprecated)]
-pub async fn open_activity_monitor(app: AppHandle) -> Result<(), String> {
+pub async fn open_activity_monitor(app: AppHandle) -> Result<(), MonitorError> {
     app.shell()
         .open("/System/Applications/Utilities/Activity Monitor.app", None)
-        .map_err(|e| e.to_string())
+        .map_err(|e| MonitorError::Internal(e.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::platform::testing::FakeProbe;
+    use crate::platform::{
+        default_probe, default_runner, CpuFrequencyReading, CpuIdentity, CpuUsageReading,
+        LoadReading, PerfLevelReading,
+    };
 
     #[test]
     fn test_get_cpu_info() {
-        let result = get_cpu_info();
+        let result = cpu_info(&*default_probe(default_runner()));
         assert!(result.is_ok());
 
         let info = result.unwrap();
         assert!(info.total_cores > 0);
         assert!(info.total_usage_percentage >= 0.0);
-        assert_eq!(info.per_core_usage.len(), info.total_cores);
+        // Offline cores have no usage
+        assert!(info.per_core_usage.len() <= info.total_cores);
     }
 
     #[test]
     fn test_get_top_cpu_processes() {
-        let result = get_top_cpu_processes(Some(5));
+        let result = top_cpu_processes(&*default_probe(default_runner()), Some(5));
         assert!(result.is_ok());
 
         let processes = result.unwrap();
@@ -188,7 +382,181 @@ mod tests {
 
     #[test]
     fn test_get_system_uptime() {
-        let uptime = get_system_uptime();
+        let uptime = default_probe(default_runner()).uptime();
         assert!(uptime > 0);
     }
+
+    #[test]
+    fn test_cpu_info_falls_back_to_load_average() {
+        let probe = FakeProbe {
+            cpu_identity: CpuIdentity {
+                model_name: "Apple M2".to_string(),
+                core_count: 8,
+            },
+            cpu_usage: None,
+            load: LoadReading {
+                one: 2.5,
+                five: 2.0,
+                fifteen: 1.5,
+            },
+            ..Default::default()
+        };
+
+        let info = cpu_info(&probe).unwrap();
+        assert_eq!(info.model_name, "Apple M2");
+        assert_eq!(info.total_usage_percentage, 25.0);
+        assert!(info.is_estimate);
+        assert!(info.breakdown.is_none());
+        assert_eq!(info.load_average.five_minutes, 2.0);
+        // No made-up per-core values when they cannot be measured
+        assert!(info.per_core_usage.is_empty());
+    }
+
+    #[test]
+    fn test_cpu_info_uses_measured_per_core_usage() {
+        let probe = FakeProbe {
+            cpu_identity: CpuIdentity {
+                model_name: "AMD Ryzen 7".to_string(),
+                core_count: 2,
+            },
+            cpu_usage: Some(CpuUsageReading {
+                total_percentage: 30.0,
+                per_core: vec![50.0, 10.0],
+                breakdown: Some(CpuBreakdownReading {
+                    user: 18.0,
+                    system: 10.0,
+                    idle: 70.0,
+                    nice: Some(0.0),
+                    iowait: Some(2.0),
+                    steal: Some(0.0),
+                }),
+            }),
+            ..Default::default()
+        };
+
+        let info = cpu_info(&probe).unwrap();
+        assert_eq!(info.total_usage_percentage, 30.0);
+        assert!(!info.is_estimate);
+        let breakdown = info.breakdown.unwrap();
+        assert_eq!(breakdown.user_percentage, 18.0);
+        assert_eq!(breakdown.iowait_percentage, Some(2.0));
+        assert_eq!(info.frequency.current_mhz, None);
+        let usage: Vec<f32> = info
+            .per_core_usage
+            .iter()
+            .map(|core| core.usage_percentage)
+            .collect();
+        assert_eq!(usage, vec![50.0, 10.0]);
+        assert!(info.per_core_usage.iter().all(|core| core.kind.is_none()));
+        assert!(info.thermal.is_none());
+    }
+
+    #[test]
+    fn test_cpu_info_tags_cores_with_their_cluster() {
+        let level = |name: &str, cores: usize| PerfLevelReading {
+            name: name.to_string(),
+            physical_cores: cores,
+            logical_cores: cores,
+            ..Default::default()
+        };
+        let probe = FakeProbe {
+            cpu_identity: CpuIdentity {
+                model_name: "Apple M1 Pro".to_string(),
+                core_count: 0,
+            },
+            cpu_topology: CpuTopologyReading {
+                physical_cores: Some(10),
+                logical_cores: Some(10),
+                perf_levels: vec![level("Performance", 8), level("Efficiency", 2)],
+                ..Default::default()
+            },
+            cpu_usage: Some(CpuUsageReading {
+                total_percentage: 20.0,
+                per_core: vec![90.0; 10],
+                ..Default::default()
+            }),
+            cpu_frequency: CpuFrequencyReading {
+                per_core_mhz: [vec![Some(2064); 2], vec![Some(3228); 7], vec![None]].concat(),
+                nominal_mhz: None,
+                max_mhz: Some(3228),
+            },
+            ..Default::default()
+        };
+
+        let info = cpu_info(&probe).unwrap();
+        assert_eq!(info.total_cores, 10);
+        assert_eq!(info.physical_cores, Some(10));
+        assert_eq!(info.performance_cores, Some(8));
+        assert_eq!(info.efficiency_cores, Some(2));
+        assert_eq!(info.clusters[1].name, "Efficiency");
+        assert_eq!(info.clusters[1].kind, CoreKind::Efficiency);
+
+        let kinds: Vec<Option<CoreKind>> =
+            info.per_core_usage.iter().map(|core| core.kind).collect();
+        assert_eq!(kinds[..2], [Some(CoreKind::Efficiency); 2]);
+        assert_eq!(kinds[2..], [Some(CoreKind::Performance); 8]);
+
+        assert_eq!(info.per_core_usage[0].frequency_mhz, Some(2064));
+        assert_eq!(info.per_core_usage[9].frequency_mhz, None);
+        assert_eq!(info.frequency.current_mhz, Some(2969));
+        assert_eq!(info.frequency.nominal_mhz, None);
+        assert_eq!(info.frequency.max_mhz, Some(3228));
+
+        // The levels must account for every core to be trusted
+        let mut probe = probe;
+        probe.cpu_usage = Some(CpuUsageReading {
+            total_percentage: 20.0,
+            per_core: vec![90.0; 8],
+            ..Default::default()
+        });
+        let info = cpu_info(&probe).unwrap();
+        assert!(info.per_core_usage.iter().all(|core| core.kind.is_none()));
+    }
+
+    #[test]
+    fn test_thermal_pressure() {
+        let pressure = |reading: ThermalReading| thermal_state(reading).pressure;
+
+        assert_eq!(
+            pressure(ThermalReading::default()),
+            ThermalPressure::Nominal
+        );
+        assert_eq!(
+            pressure(ThermalReading {
+                cpu_speed_limit: Some(100),
+                scheduler_limit: Some(100),
+                thermal_warning_level: Some(0),
+                ..Default::default()
+            }),
+            ThermalPressure::Nominal
+        );
+        assert_eq!(
+            pressure(ThermalReading {
+                scheduler_limit: Some(80),
+                ..Default::default()
+            }),
+            ThermalPressure::Moderate
+        );
+        assert_eq!(
+            pressure(ThermalReading {
+                cpu_speed_limit: Some(52),
+                ..Default::default()
+            }),
+            ThermalPressure::Heavy
+        );
+        assert_eq!(
+            pressure(ThermalReading {
+                thermal_warning_level: Some(10),
+                ..Default::default()
+            }),
+            ThermalPressure::Critical
+        );
+
+        let state = thermal_state(ThermalReading {
+            cpu_speed_limit: Some(78),
+            ..Default::default()
+        });
+        assert!(state.is_throttled);
+        assert_eq!(state.cpu_speed_limit_percentage, Some(78));
+    }
 }
@@ -1,8 +1,11 @@
 use serde::Serialize;
-use sysinfo::Disks;
-use tauri::AppHandle;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
 use tauri_plugin_shell::ShellExt;
 
+use crate::error::MonitorError;
+use crate::platform::{SharedProbe, SystemProbe};
+
 #[derive(Debug, Clone, Serialize)]
 pub struct DiskInfo {
     pub name: String,
@@ -26,14 +29,17 @@ pub struct DisksOverview {
 }
 
 #[tauri::command]
-pub fn get_disk_info() -> Result<DisksOverview, String> {
-    let disks = Disks::new_with_refreshed_list();
+pub fn get_disk_info(probe: State<'_, SharedProbe>) -> Result<DisksOverview, MonitorError> {
+    disk_info(&**probe)
+}
 
-    let mut all_disks: Vec<DiskInfo> = disks
-        .iter()
+pub fn disk_info(probe: &dyn SystemProbe) -> Result<DisksOverview, MonitorError> {
+    let mut all_disks: Vec<DiskInfo> = probe
+        .disks()
+        .into_iter()
         .map(|disk| {
-            let total = disk.total_space();
-            let available = disk.available_space();
+            let total = disk.total_bytes;
+            let available = disk.available_bytes;
             let used = total.saturating_sub(available);
             let used_percentage = if total > 0 {
                 (used as f32 / total as f32) * 100.0
@@ -42,14 +48,14 @@ pub fn get_disk_info() -> Result<DisksOverview, String> {
             };
 
             DiskInfo {
-                name: disk.name().to_string_lossy().to_string(),
-                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                name: disk.name,
+                mount_point: disk.mount_point,
                 total_bytes: total,
                 available_bytes: available,
                 used_bytes: used,
                 used_percentage,
-                file_system: disk.file_system().to_string_lossy().to_string(),
-                is_removable: disk.is_removable(),
+                file_system: disk.file_system,
+                is_removable: disk.is_removable,
             }
         })
         .collect();
@@ -99,30 +105,6 @@ pub struct StorageCategories {
     pub total_categorized: u64,
 }
 
-/// Get directory size using du -s (fast, single line output)
-fn get_dir_size(path: &std::path::Path) -> u64 {
-    if !path.exists() {
-        return 0;
-    }
-
-    let output = std::process::Command::new("du")
-        .args(["-sk", path.to_string_lossy().as_ref()])
-        .output();
-
-    match output {
-        Ok(out) if out.status.success() => {
-            let stdout = String::from_utf8_lossy(&out.stdout);
-            stdout
-                .split_whitespace()
-                .next()
-                .and_then(|s| s.parse::<u64>().ok())
-                .map(|kb| kb * 1024)
-                .unwrap_or(0)
-        }
-        _ => 0,
-    }
-}
-
 #[derive(Debug, Clone, Serialize, serde::Deserialize)]
 struct CachedStorageCategories {
     categories: StorageCategories,
@@ -157,7 +139,7 @@ fn write_cache(categories: &StorageCategories) {
     }
 }
 
-fn calculate_categories() -> StorageCategories {
+fn calculate_categories(probe: &SharedProbe) -> StorageCategories {
     let home = dirs::home_dir().unwrap_or_default();
 
     let dirs_to_scan: Vec<(&str, std::path::PathBuf, &str)> = vec![
@@ -180,9 +162,10 @@ fn calculate_categories() -> StorageCategories {
         .map(|(name, path, color)| {
             let name = name.to_string();
             let color = color.to_string();
+            let probe = Arc::clone(probe);
             std::thread::spawn(move || StorageCategory {
                 name,
-                bytes: get_dir_size(&path),
+                bytes: probe.dir_size(&path),
                 color,
             })
         })
@@ -201,7 +184,9 @@ fn calculate_categories() -> StorageCategories {
 
 /// Get storage categories - returns cached data immediately, refreshes in background if stale
 #[tauri::command]
-pub fn get_storage_categories() -> Result<StorageCategories, String> {
+pub fn get_storage_categories(
+    probe: State<'_, SharedProbe>,
+) -> Result<StorageCategories, MonitorError> {
     // Return cached data if available (less than 5 minutes old)
     if let Some(cached) = read_cache() {
         let now = std::time::SystemTime::now()
@@ -216,16 +201,18 @@ pub fn get_storage_categories() -> Result<StorageCategories, String> {
         }
 
         // Cache is stale, return it but trigger background refresh
-        std::thread::spawn(|| {
-            let categories = calculate_categories();
+        let probe = Arc::clone(&probe);
+        std::thread::spawn(move || {
+            let categories = calculate_categories(&probe);
             write_cache(&categories);
         });
         return Ok(cached.categories);
     }
 
     // No cache - return empty and trigger background calculation
-    std::thread::spawn(|| {
-        let categories = calculate_categories();
+    let probe = Arc::clone(&probe);
+    std::thread::spawn(move || {
+        let categories = calculate_categories(&probe);
         write_cache(&categories);
     });
 
@@ -237,23 +224,25 @@ pub fn get_storage_categories() -> Result<StorageCategories, String> {
 
 /// Force refresh storage categories (called when user wants fresh data)
 #[tauri::command]
-pub fn refresh_storage_categories() -> Result<StorageCategories, String> {
-    let categories = calculate_categories();
+pub fn refresh_storage_categories(
+    probe: State<'_, SharedProbe>,
+) -> Result<StorageCategories, MonitorError> {
+    let categories = calculate_categories(&probe);
     write_cache(&categories);
     Ok(categories)
 }
 
 #[tauri::command]
 #[allow(deprecated)]
-pub async fn open_storage_settings(app: AppHandle) -> Result<(), String> {
+pub async fn open_storage_settings(app: AppHandle) -> Result<(), MonitorError> {
     app.shell()
         .open("x-apple.systempreferences:com.apple.settings.Storage", None)
-        .map_err(|e| e.to_string())
+        .map_err(|e| MonitorError::Internal(e.to_string()))
 }
 
 #[tauri::command]
 #[allow(deprecated)]
-pub async fn open_system_settings(app: AppHandle, panel: String) -> Result<(), String> {
+pub async fn open_system_settings(app: AppHandle, panel: String) -> Result<(), MonitorError> {
     let url = match panel.as_str() {
         "storage" => "x-apple.systempreferences:com.apple.settings.Storage",
         "privacy" => "x-apple.systempreferences:com.apple.preference.security?Privacy",
@@ -263,19 +252,28 @@ pub async fn open_system_settings(app: AppHandle, panel: String) -> Result<(), S
         "full-disk-access" => {
             "x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles"
         }
-        _ => return Err(format!("Unknown panel: {}", panel)),
+        _ => {
+            return Err(MonitorError::InvalidArgument(format!(
+                "Unknown panel: {}",
+                panel
+            )))
+        }
     };
 
-    app.shell().open(url, None).map_err(|e| e.to_string())
+    app.shell()
+        .open(url, None)
+        .map_err(|e| MonitorError::Internal(e.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::platform::testing::FakeProbe;
+    use crate::platform::{default_probe, default_runner, DiskReading};
 
     #[test]
     fn test_get_disk_info() {
-        let result = get_disk_info();
+        let result = disk_info(&*default_probe(default_runner()));
         assert!(result.is_ok());
 
         let overview = result.unwrap();
@@ -283,4 +281,29 @@ mod tests {
         assert!(overview.primary.is_some());
         assert!(overview.total_space_bytes > 0);
     }
+
+    #[test]
+    fn test_disk_info_excludes_removable_from_totals() {
+        let disk = |name: &str, mount_point: &str, total_bytes, is_removable| DiskReading {
+            name: name.to_string(),
+            mount_point: mount_point.to_string(),
+            total_bytes,
+            available_bytes: total_bytes / 4,
+            file_system: "apfs".to_string(),
+            is_removable,
+        };
+        let probe = FakeProbe {
+            disks: vec![
+                disk("Backup", "/Volumes/Backup", 2000, true),
+                disk("Macintosh HD", "/", 1000, false),
+            ],
+            ..Default::default()
+        };
+
+        let overview = disk_info(&probe).unwrap();
+        assert_eq!(overview.all_disks[0].name, "Backup");
+        assert_eq!(overview.primary.unwrap().mount_point, "/");
+        assert_eq!(overview.total_space_bytes, 1000);
+        assert_eq!(overview.total_used_percentage, 75.0);
+    }
 }
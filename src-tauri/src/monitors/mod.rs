@@ -1,4 +1,6 @@
 pub mod battery;
 pub mod cpu;
 pub mod disk;
+pub mod power;
 pub mod ram;
+pub mod sleep;
@@ -0,0 +1,128 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::error::MonitorError;
+use crate::platform::{DrawingFrom, SharedProbe, SystemProbe};
+
+/// What the machine is running on right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    InternalBattery,
+    Ac,
+    Ups,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerSourceInfo {
+    pub source: PowerSource,
+    pub has_internal_battery: bool,
+    /// The connected UPS, if any
+    pub ups: Option<UpsInfo>,
+}
+
+/// An uninterruptible power supply connected over USB.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpsInfo {
+    pub name: String,
+    pub percentage: Option<f32>,
+    /// Mains power is out and the UPS is carrying the machine
+    pub is_on_battery: bool,
+    pub is_charging: bool,
+    /// Runtime left while on battery, time to full while charging
+    pub time_remaining_minutes: Option<u32>,
+}
+
+#[tauri::command]
+pub fn get_power_source(probe: State<'_, SharedProbe>) -> Result<PowerSourceInfo, MonitorError> {
+    power_source_info(&**probe)
+}
+
+pub fn power_source_info(probe: &dyn SystemProbe) -> Result<PowerSourceInfo, MonitorError> {
+    let reading = match probe.power_sources()? {
+        Some(reading) => reading,
+        None => {
+            // Without a listing only the internal battery is known
+            let battery = probe.battery()?;
+            let source = match &battery {
+                Some(battery) if !battery.external_connected => PowerSource::InternalBattery,
+                _ => PowerSource::Ac,
+            };
+            return Ok(PowerSourceInfo {
+                source,
+                has_internal_battery: battery.is_some(),
+                ups: None,
+            });
+        }
+    };
+
+    let is_on_ups = reading.drawing_from == DrawingFrom::Ups;
+    let ups = reading
+        .sources
+        .iter()
+        .find(|source| !source.is_internal)
+        .map(|source| UpsInfo {
+            name: source.name.clone(),
+            percentage: source.percentage,
+            is_on_battery: is_on_ups,
+            is_charging: source.is_charging,
+            time_remaining_minutes: source.time_remaining_minutes,
+        });
+
+    Ok(PowerSourceInfo {
+        source: match reading.drawing_from {
+            DrawingFrom::Ac => PowerSource::Ac,
+            DrawingFrom::Battery => PowerSource::InternalBattery,
+            DrawingFrom::Ups => PowerSource::Ups,
+        },
+        has_internal_battery: reading.sources.iter().any(|source| source.is_internal),
+        ups,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::testing::FakeProbe;
+    use crate::platform::{BatteryReading, PowerSourceReading, PowerSourcesReading};
+
+    #[test]
+    fn test_ups_on_battery() {
+        let probe = FakeProbe {
+            power_sources: Some(PowerSourcesReading {
+                drawing_from: DrawingFrom::Ups,
+                sources: vec![PowerSourceReading {
+                    name: "Back-UPS ES 700G".to_string(),
+                    percentage: Some(87.0),
+                    time_remaining_minutes: Some(14),
+                    ..Default::default()
+                }],
+            }),
+            ..Default::default()
+        };
+
+        let info = power_source_info(&probe).unwrap();
+        assert_eq!(info.source, PowerSource::Ups);
+        assert!(!info.has_internal_battery);
+        let ups = info.ups.unwrap();
+        assert!(ups.is_on_battery);
+        assert_eq!(ups.percentage, Some(87.0));
+        assert_eq!(ups.time_remaining_minutes, Some(14));
+    }
+
+    #[test]
+    fn test_falls_back_to_battery_reading() {
+        let probe = FakeProbe {
+            battery: Some(BatteryReading::default()),
+            ..Default::default()
+        };
+        let info = power_source_info(&probe).unwrap();
+        assert_eq!(info.source, PowerSource::InternalBattery);
+        assert!(info.has_internal_battery);
+        assert!(info.ups.is_none());
+
+        let info = power_source_info(&FakeProbe::default()).unwrap();
+        assert_eq!(info.source, PowerSource::Ac);
+        assert!(!info.has_internal_battery);
+    }
+}
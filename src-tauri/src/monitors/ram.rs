@@ -1,8 +1,10 @@
 use nix::sys::signal::{kill, Signal};
 use nix::unistd::Pid;
 use serde::Serialize;
-use std::process::Command;
-use sysinfo::System;
+use tauri::State;
+
+use crate::error::MonitorError;
+use crate::platform::{run_with_admin, CommandRunner, SharedProbe, SharedRunner, SystemProbe};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct RamInfo {
@@ -23,12 +25,15 @@ pub struct ProcessMemoryInfo {
 }
 
 #[tauri::command]
-pub fn get_ram_info() -> Result<RamInfo, String> {
-    let mut sys = System::new();
-    sys.refresh_memory();
+pub fn get_ram_info(probe: State<'_, SharedProbe>) -> Result<RamInfo, MonitorError> {
+    ram_info(&**probe)
+}
+
+pub fn ram_info(probe: &dyn SystemProbe) -> Result<RamInfo, MonitorError> {
+    let memory = probe.memory()?;
 
-    let total = sys.total_memory();
-    let used = sys.used_memory();
+    let total = memory.total_bytes;
+    let used = memory.used_bytes;
     // Always calculate available as total - used for UI consistency
     // (macOS available_memory() returns only truly free memory, excluding cached files)
     let available = total.saturating_sub(used);
@@ -58,57 +63,47 @@ pub fn get_ram_info() -> Result<RamInfo, String> {
 }
 
 #[tauri::command]
-pub fn get_top_memory_processes(count: Option<usize>) -> Result<Vec<ProcessMemoryInfo>, String> {
-    let count = count.unwrap_or(10);
-
-    // Use ps command - much faster than sysinfo for process listing
-    let output = Command::new("ps")
-        .args(["-axm", "-o", "pid,rss,command"])
-        .output()
-        .map_err(|e| format!("Failed to run ps: {}", e))?;
+pub fn get_top_memory_processes(
+    probe: State<'_, SharedProbe>,
+    count: Option<usize>,
+) -> Result<Vec<ProcessMemoryInfo>, MonitorError> {
+    top_memory_processes(&**probe, count)
+}
 
-    if !output.status.success() {
-        return Err("ps command failed".to_string());
-    }
+pub fn top_memory_processes(
+    probe: &dyn SystemProbe,
+    count: Option<usize>,
+) -> Result<Vec<ProcessMemoryInfo>, MonitorError> {
+    let count = count.unwrap_or(10);
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut sys = System::new();
-    sys.refresh_memory();
-    let total_memory = sys.total_memory();
-
-    let mut processes: Vec<ProcessMemoryInfo> = stdout
-        .lines()
-        .skip(1) // Skip header
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                let pid = parts[0].parse::<u32>().ok()?;
-                let rss_kb = parts[1].parse::<u64>().ok()?;
-                let memory_bytes = rss_kb * 1024;
-                let full_path = parts[2..].join(" ");
-                // Extract just the executable name from the path
-                let name = full_path
-                    .split('/')
-                    .next_back()
-                    .unwrap_or(&full_path)
-                    .split(' ')
-                    .next()
-                    .unwrap_or(&full_path)
-                    .to_string();
-                let memory_percentage = if total_memory > 0 {
-                    (memory_bytes as f32 / total_memory as f32) * 100.0
-                } else {
-                    0.0
-                };
-                Some(ProcessMemoryInfo {
-                    pid,
-                    name,
-                    path: full_path,
-                    memory_bytes,
-                    memory_percentage,
-                })
+    let readings = probe.memory_processes()?;
+    let total_memory = probe.memory()?.total_bytes;
+
+    let mut processes: Vec<ProcessMemoryInfo> = readings
+        .into_iter()
+        .map(|reading| {
+            let full_path = reading.command;
+            // Extract just the executable name from the path
+            let name = full_path
+                .split('/')
+                .next_back()
+                .unwrap_or(&full_path)
+                .split(' ')
+                .next()
+                .unwrap_or(&full_path)
+                .to_string();
+            let memory_bytes = reading.rss_bytes;
+            let memory_percentage = if total_memory > 0 {
+                (memory_bytes as f32 / total_memory as f32) * 100.0
             } else {
-                None
+                0.0
+            };
+            ProcessMemoryInfo {
+                pid: reading.pid,
+                name,
+                path: full_path,
+                memory_bytes,
+                memory_percentage,
             }
         })
         .collect();
@@ -127,42 +122,41 @@ pub struct MemoryCleanResult {
 }
 
 /// Get current used memory for calculating freed bytes
-fn get_used_memory() -> u64 {
-    let mut sys = System::new();
-    sys.refresh_memory();
-    sys.used_memory()
+fn get_used_memory(probe: &dyn SystemProbe) -> u64 {
+    probe.memory().map(|m| m.used_bytes).unwrap_or(0)
 }
 
 #[tauri::command]
-pub fn purge_memory_with_admin() -> Result<MemoryCleanResult, String> {
-    let before = get_used_memory();
-
-    // Use osascript to run purge with admin privileges
-    // This will prompt the user for their password
-    let script = r#"do shell script "purge" with administrator privileges"#;
-
-    let output = Command::new("osascript")
-        .args(["-e", script])
-        .output()
-        .map_err(|e| format!("Failed to execute osascript: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        // User cancelled or authentication failed
-        if stderr.contains("canceled") || stderr.contains("User canceled") {
-            return Ok(MemoryCleanResult {
-                success: false,
-                freed_bytes: 0,
-                message: "Authentication cancelled".to_string(),
-            });
-        }
-        return Err(format!("Purge failed: {}", stderr));
+pub fn purge_memory_with_admin(
+    probe: State<'_, SharedProbe>,
+    runner: State<'_, SharedRunner>,
+) -> Result<MemoryCleanResult, MonitorError> {
+    // purge and the osascript privilege prompt only exist on macOS
+    if !cfg!(target_os = "macos") {
+        return Err(MonitorError::Unsupported("Memory purge".to_string()));
+    }
+    purge_memory(&**probe, &**runner)
+}
+
+pub fn purge_memory(
+    probe: &dyn SystemProbe,
+    runner: &dyn CommandRunner,
+) -> Result<MemoryCleanResult, MonitorError> {
+    let before = get_used_memory(probe);
+
+    // Prompts the user for their password
+    if !run_with_admin(runner, "purge")? {
+        return Ok(MemoryCleanResult {
+            success: false,
+            freed_bytes: 0,
+            message: "Authentication cancelled".to_string(),
+        });
     }
 
     // Wait a moment for memory to settle
     std::thread::sleep(std::time::Duration::from_millis(500));
 
-    let after = get_used_memory();
+    let after = get_used_memory(probe);
     let freed_bytes = before.saturating_sub(after);
 
     Ok(MemoryCleanResult {
@@ -179,7 +173,7 @@ pub struct ForceQuitResult {
 }
 
 #[tauri::command]
-pub fn force_quit_process(pid: u32) -> Result<ForceQuitResult, String> {
+pub fn force_quit_process(pid: u32) -> Result<ForceQuitResult, MonitorError> {
     let nix_pid = Pid::from_raw(pid as i32);
 
     // First try SIGTERM (graceful termination)
@@ -197,7 +191,10 @@ pub fn force_quit_process(pid: u32) -> Result<ForceQuitResult, String> {
                             success: true,
                             message: "Process force killed".to_string(),
                         }),
-                        Err(e) => Err(format!("Failed to kill process: {}", e)),
+                        Err(e) => Err(MonitorError::Internal(format!(
+                            "Failed to kill process: {}",
+                            e
+                        ))),
                     }
                 }
                 Err(_) => {
@@ -223,7 +220,10 @@ pub fn force_quit_process(pid: u32) -> Result<ForceQuitResult, String> {
                 message: "Permission denied. Try granting Accessibility access.".to_string(),
             })
         }
-        Err(e) => Err(format!("Failed to terminate process: {}", e)),
+        Err(e) => Err(MonitorError::Internal(format!(
+            "Failed to terminate process: {}",
+            e
+        ))),
     }
 }
 
@@ -231,9 +231,12 @@ pub fn force_quit_process(pid: u32) -> Result<ForceQuitResult, String> {
 mod tests {
     use super::*;
 
+    use crate::platform::testing::{replay, FakeProbe};
+    use crate::platform::{default_probe, default_runner, MemoryReading, ProcessMemoryReading};
+
     #[test]
     fn test_get_ram_info() {
-        let result = get_ram_info();
+        let result = ram_info(&*default_probe(default_runner()));
         assert!(result.is_ok());
 
         let info = result.unwrap();
@@ -244,7 +247,7 @@ mod tests {
 
     #[test]
     fn test_get_top_memory_processes() {
-        let result = get_top_memory_processes(Some(5));
+        let result = top_memory_processes(&*default_probe(default_runner()), Some(5));
         assert!(result.is_ok());
 
         let processes = result.unwrap();
@@ -255,4 +258,57 @@ mod tests {
             assert!(processes[i - 1].memory_bytes >= processes[i].memory_bytes);
         }
     }
+
+    #[test]
+    fn test_ram_info_pressure_levels() {
+        let gib = 1024 * 1024 * 1024;
+        let probe = FakeProbe {
+            memory: MemoryReading {
+                total_bytes: 16 * gib,
+                used_bytes: 14 * gib,
+            },
+            ..Default::default()
+        };
+
+        let info = ram_info(&probe).unwrap();
+        assert_eq!(info.available_bytes, 2 * gib);
+        assert_eq!(info.used_percentage, 87.5);
+        assert_eq!(info.pressure_level, "critical");
+    }
+
+    #[test]
+    fn test_top_memory_processes_names_and_order() {
+        let probe = FakeProbe {
+            memory: MemoryReading {
+                total_bytes: 1000 * 1024,
+                used_bytes: 0,
+            },
+            memory_processes: vec![
+                ProcessMemoryReading {
+                    pid: 1,
+                    command: "/usr/libexec/small --flag".to_string(),
+                    rss_bytes: 100 * 1024,
+                },
+                ProcessMemoryReading {
+                    pid: 2,
+                    command: "/Applications/Big.app/Contents/MacOS/Big".to_string(),
+                    rss_bytes: 500 * 1024,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let processes = top_memory_processes(&probe, Some(1)).unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid, 2);
+        assert_eq!(processes[0].name, "Big");
+        assert_eq!(processes[0].memory_percentage, 50.0);
+    }
+
+    #[test]
+    fn test_purge_memory_cancelled() {
+        let result = purge_memory(&FakeProbe::default(), &replay("macbook")).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.message, "Authentication cancelled");
+    }
 }
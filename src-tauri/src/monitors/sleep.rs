@@ -0,0 +1,351 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::error::MonitorError;
+use crate::history::battery::{time_range, BatteryHistory, SharedBatteryHistory};
+use crate::platform::{SharedProbe, SleepEventKind, SleepEventReading, SystemProbe};
+
+/// A battery sample this close to an event stands in for the charge the log
+/// didn't record.
+const SAMPLE_TOLERANCE_MS: u64 = 10 * 60 * 1000;
+
+/// Assertions that keep the display awake, and with it the system.
+const DISPLAY_ASSERTIONS: &[&str] = &["PreventUserIdleDisplaySleep", "NoDisplaySleepAssertion"];
+
+/// Assertions that keep the system awake with the display off.
+const SYSTEM_ASSERTIONS: &[&str] = &[
+    "PreventUserIdleSystemSleep",
+    "PreventSystemSleep",
+    "NoIdleSleepAssertion",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SleepEventType {
+    Sleep,
+    Wake,
+    DarkWake,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepEvent {
+    pub timestamp_ms: u64,
+    #[serde(rename = "type")]
+    pub event_type: SleepEventType,
+    pub reason: String,
+    pub percentage: Option<f32>,
+}
+
+/// From going to sleep until the next full wake. Dark wakes in between
+/// don't end it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepPeriod {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub duration_minutes: u32,
+    pub sleep_reason: String,
+    pub wake_reason: String,
+    pub dark_wake_count: u32,
+    /// No event in the period was logged on AC power
+    pub on_battery: bool,
+    pub start_percentage: Option<f32>,
+    pub end_percentage: Option<f32>,
+    /// Charge lost while asleep, only known on battery
+    pub drain_percentage: Option<f32>,
+    pub drain_per_hour_percentage: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepTimeline {
+    /// Oldest first
+    pub events: Vec<SleepEvent>,
+    pub periods: Vec<SleepPeriod>,
+}
+
+/// A process keeping the Mac from sleeping.
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepAssertion {
+    /// For `force_quit_process`
+    pub pid: u32,
+    pub process_name: String,
+    pub assertion_type: String,
+    pub name: Option<String>,
+    pub held_seconds: Option<u64>,
+    pub prevents_display_sleep: bool,
+}
+
+#[tauri::command]
+pub fn get_sleep_assertions(
+    probe: State<'_, SharedProbe>,
+) -> Result<Vec<SleepAssertion>, MonitorError> {
+    sleep_assertions(&**probe)
+}
+
+/// Sleep-preventing assertions, longest held first.
+pub fn sleep_assertions(probe: &dyn SystemProbe) -> Result<Vec<SleepAssertion>, MonitorError> {
+    let mut assertions: Vec<SleepAssertion> = probe
+        .assertions()?
+        .into_iter()
+        .filter_map(|assertion| {
+            let kind = assertion.assertion_type.as_str();
+            let prevents_display_sleep = DISPLAY_ASSERTIONS.contains(&kind);
+            if !prevents_display_sleep && !SYSTEM_ASSERTIONS.contains(&kind) {
+                return None;
+            }
+            Some(SleepAssertion {
+                pid: assertion.pid,
+                process_name: assertion.process_name,
+                assertion_type: assertion.assertion_type,
+                name: assertion.name,
+                held_seconds: assertion.held_seconds,
+                prevents_display_sleep,
+            })
+        })
+        .collect();
+    assertions.sort_by(|a, b| b.held_seconds.cmp(&a.held_seconds));
+    Ok(assertions)
+}
+
+#[tauri::command(async)]
+pub fn get_sleep_timeline(
+    probe: State<'_, SharedProbe>,
+    history: State<'_, SharedBatteryHistory>,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+) -> Result<SleepTimeline, MonitorError> {
+    let (from_ms, to_ms) = time_range(from_ms, to_ms)?;
+    sleep_timeline(&**probe, &history, from_ms, to_ms)
+}
+
+pub fn sleep_timeline(
+    probe: &dyn SystemProbe,
+    history: &BatteryHistory,
+    from_ms: u64,
+    to_ms: u64,
+) -> Result<SleepTimeline, MonitorError> {
+    let mut log = probe.sleep_log()?;
+    log.sort_by_key(|event| event.timestamp_ms);
+
+    // The log records the charge on most machines, the battery history
+    // fills in where it doesn't
+    let percentage = |event: &SleepEventReading| {
+        event.charge_percentage.or_else(|| {
+            history
+                .sample_near(event.timestamp_ms, SAMPLE_TOLERANCE_MS)
+                .map(|sample| sample.percentage)
+        })
+    };
+
+    let events = log
+        .iter()
+        .filter(|event| (from_ms..=to_ms).contains(&event.timestamp_ms))
+        .map(|event| SleepEvent {
+            timestamp_ms: event.timestamp_ms,
+            event_type: match event.kind {
+                SleepEventKind::Sleep => SleepEventType::Sleep,
+                SleepEventKind::Wake => SleepEventType::Wake,
+                SleepEventKind::DarkWake => SleepEventType::DarkWake,
+            },
+            reason: event.reason.clone(),
+            percentage: percentage(event),
+        })
+        .collect();
+
+    let periods = derive_periods(&log, percentage)
+        .into_iter()
+        .filter(|period| period.end_ms >= from_ms && period.start_ms <= to_ms)
+        .collect();
+
+    Ok(SleepTimeline { events, periods })
+}
+
+fn derive_periods(
+    log: &[SleepEventReading],
+    percentage: impl Fn(&SleepEventReading) -> Option<f32>,
+) -> Vec<SleepPeriod> {
+    let mut periods = Vec::new();
+    // The sleep that started the current period, the dark wakes since and
+    // whether everything so far was on battery
+    let mut asleep: Option<(&SleepEventReading, u32, bool)> = None;
+
+    for event in log {
+        let on_battery = event.on_battery != Some(false);
+        match (event.kind, asleep.as_mut()) {
+            (SleepEventKind::Sleep, None) => asleep = Some((event, 0, on_battery)),
+            // Back to sleep after a dark wake
+            (SleepEventKind::Sleep, Some((_, _, all_on_battery))) => {
+                *all_on_battery &= on_battery;
+            }
+            (SleepEventKind::DarkWake, Some((_, dark_wakes, all_on_battery))) => {
+                *dark_wakes += 1;
+                *all_on_battery &= on_battery;
+            }
+            (SleepEventKind::Wake, Some(&mut (sleep, dark_wake_count, all_on_battery))) => {
+                let on_battery = all_on_battery && on_battery;
+                let start_percentage = percentage(sleep);
+                let end_percentage = percentage(event);
+                let duration_ms = event.timestamp_ms - sleep.timestamp_ms;
+
+                let drain_percentage = match (start_percentage, end_percentage) {
+                    (Some(start), Some(end)) if on_battery => Some(start - end),
+                    _ => None,
+                };
+                let hours = duration_ms as f32 / 3_600_000.0;
+                let drain_per_hour_percentage = drain_percentage
+                    .filter(|_| hours > 0.0)
+                    .map(|drain| drain / hours);
+
+                periods.push(SleepPeriod {
+                    start_ms: sleep.timestamp_ms,
+                    end_ms: event.timestamp_ms,
+                    duration_minutes: (duration_ms / 60_000) as u32,
+                    sleep_reason: sleep.reason.clone(),
+                    wake_reason: event.reason.clone(),
+                    dark_wake_count,
+                    on_battery,
+                    start_percentage,
+                    end_percentage,
+                    drain_percentage,
+                    drain_per_hour_percentage,
+                });
+                asleep = None;
+            }
+            // A wake without a logged sleep, the log starts mid-period
+            (SleepEventKind::Wake | SleepEventKind::DarkWake, None) => {}
+        }
+    }
+
+    periods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::battery::BatterySample;
+    use crate::platform::testing::FakeProbe;
+    use crate::platform::AssertionReading;
+
+    const HOUR_MS: u64 = 3_600_000;
+
+    fn event(
+        hour: u64,
+        kind: SleepEventKind,
+        charge: Option<f32>,
+        on_battery: bool,
+    ) -> SleepEventReading {
+        SleepEventReading {
+            timestamp_ms: hour * HOUR_MS,
+            kind,
+            reason: format!("{:?}", kind),
+            charge_percentage: charge,
+            on_battery: Some(on_battery),
+        }
+    }
+
+    #[test]
+    fn test_overnight_drain() {
+        let probe = FakeProbe {
+            sleep_log: vec![
+                event(1, SleepEventKind::Sleep, Some(90.0), true),
+                event(3, SleepEventKind::DarkWake, Some(88.0), true),
+                event(3, SleepEventKind::Sleep, Some(88.0), true),
+                event(9, SleepEventKind::Wake, Some(82.0), true),
+            ],
+            ..Default::default()
+        };
+        let timeline = sleep_timeline(&probe, &BatteryHistory::in_memory(), 0, u64::MAX).unwrap();
+
+        assert_eq!(timeline.events.len(), 4);
+        assert_eq!(timeline.events[1].event_type, SleepEventType::DarkWake);
+        assert_eq!(timeline.periods.len(), 1);
+        let period = &timeline.periods[0];
+        assert_eq!(period.duration_minutes, 8 * 60);
+        assert_eq!(period.dark_wake_count, 1);
+        assert_eq!(period.sleep_reason, "Sleep");
+        assert_eq!(period.wake_reason, "Wake");
+        assert_eq!(period.drain_percentage, Some(8.0));
+        assert_eq!(period.drain_per_hour_percentage, Some(1.0));
+    }
+
+    #[test]
+    fn test_sleep_assertions() {
+        let assertion =
+            |pid: u32, assertion_type: &str, held_seconds: Option<u64>| AssertionReading {
+                pid,
+                process_name: format!("process-{}", pid),
+                assertion_type: assertion_type.to_string(),
+                name: None,
+                held_seconds,
+            };
+        let probe = FakeProbe {
+            assertions: vec![
+                assertion(88, "PreventUserIdleSystemSleep", Some(60)),
+                assertion(318, "UserIsActive", Some(7200)),
+                assertion(1021, "PreventUserIdleDisplaySleep", Some(3600)),
+                assertion(412, "NoIdleSleepAssertion", None),
+            ],
+            ..Default::default()
+        };
+
+        let assertions = sleep_assertions(&probe).unwrap();
+        let pids: Vec<u32> = assertions.iter().map(|a| a.pid).collect();
+        assert_eq!(pids, vec![1021, 88, 412]);
+        assert!(assertions[0].prevents_display_sleep);
+        assert!(!assertions[1].prevents_display_sleep);
+    }
+
+    #[test]
+    fn test_charge_from_battery_history() {
+        let history = BatteryHistory::in_memory();
+        for (minute, percentage) in [(58, 70.0), (185, 66.0)] {
+            history.record(BatterySample {
+                timestamp_ms: minute * 60_000,
+                percentage,
+                is_charging: false,
+                is_plugged_in: false,
+                power_draw_watts: None,
+                temperature_celsius: None,
+            });
+        }
+        let probe = FakeProbe {
+            sleep_log: vec![
+                event(1, SleepEventKind::Sleep, None, true),
+                event(3, SleepEventKind::Wake, None, true),
+            ],
+            ..Default::default()
+        };
+
+        let period = sleep_timeline(&probe, &history, 0, u64::MAX)
+            .unwrap()
+            .periods
+            .remove(0);
+        assert_eq!(period.start_percentage, Some(70.0));
+        assert_eq!(period.end_percentage, Some(66.0));
+        assert_eq!(period.drain_percentage, Some(4.0));
+    }
+
+    #[test]
+    fn test_no_drain_on_ac() {
+        let probe = FakeProbe {
+            sleep_log: vec![
+                // The log starts while awake
+                event(0, SleepEventKind::Wake, Some(50.0), true),
+                event(1, SleepEventKind::Sleep, Some(50.0), true),
+                event(2, SleepEventKind::DarkWake, Some(55.0), false),
+                event(4, SleepEventKind::Wake, Some(60.0), false),
+                event(6, SleepEventKind::Sleep, Some(60.0), true),
+            ],
+            ..Default::default()
+        };
+        let timeline = sleep_timeline(&probe, &BatteryHistory::in_memory(), 0, u64::MAX).unwrap();
+
+        assert_eq!(timeline.periods.len(), 1);
+        assert!(!timeline.periods[0].on_battery);
+        assert_eq!(timeline.periods[0].drain_percentage, None);
+
+        let timeline =
+            sleep_timeline(&probe, &BatteryHistory::in_memory(), 5 * HOUR_MS, u64::MAX).unwrap();
+        assert_eq!(timeline.events.len(), 1);
+        assert!(timeline.periods.is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+//! Typed model of the `AppleSmartBattery` IORegistry entry.
+//!
+//! Read from `ioreg -a -rc AppleSmartBattery`, which prints the matching
+//! entries as an XML plist array, so nested dictionaries such as
+//! `BatteryData` come back intact instead of having to be scraped from text.
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+use crate::error::MonitorError;
+
+use super::{AdapterReading, BatteryIdentity, BatteryReading, ManufactureDate};
+
+/// `TimeRemaining` and friends use this while the gauge is still estimating.
+const TIME_UNKNOWN: u32 = 65535;
+
+/// The gauge properties of the battery. Every field is optional because the
+/// set of keys differs between hardware generations and macOS releases.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct AppleSmartBattery {
+    /// Factory capacity in mAh
+    pub design_capacity: Option<u32>,
+    /// Remaining charge in mAh
+    #[serde(rename = "AppleRawCurrentCapacity")]
+    pub raw_current_capacity: Option<u32>,
+    /// Full charge capacity in mAh
+    #[serde(rename = "AppleRawMaxCapacity")]
+    pub raw_max_capacity: Option<u32>,
+    pub nominal_charge_capacity: Option<u32>,
+    pub is_charging: Option<bool>,
+    pub external_connected: Option<bool>,
+    pub cycle_count: Option<u32>,
+    /// Hundredths of a degree Celsius
+    pub temperature: Option<u32>,
+    /// Millivolts
+    pub voltage: Option<u32>,
+    /// Milliamps, negative while discharging
+    #[serde(deserialize_with = "signed")]
+    pub amperage: Option<i64>,
+    #[serde(deserialize_with = "signed")]
+    pub instant_amperage: Option<i64>,
+    /// Minutes, [`TIME_UNKNOWN`] while estimating
+    pub time_remaining: Option<u32>,
+    pub avg_time_to_empty: Option<u32>,
+    pub avg_time_to_full: Option<u32>,
+    pub battery_data: Option<BatteryData>,
+    pub power_telemetry_data: Option<PowerTelemetryData>,
+    /// Describes the last adapter seen, even after it is unplugged
+    pub adapter_details: Option<AdapterDetails>,
+    pub serial: Option<String>,
+    pub manufacturer: Option<String>,
+    pub device_name: Option<String>,
+    /// Smart Battery packed date, see [`decode_manufacture_date`]
+    pub manufacture_date: Option<u32>,
+}
+
+/// The `BatteryData` dictionary reported by the gas gauge itself.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BatteryData {
+    pub cycle_count: Option<u32>,
+    pub design_capacity: Option<u32>,
+    /// Millivolts
+    pub voltage: Option<u32>,
+    /// Per-cell voltages in millivolts
+    pub cell_voltage: Option<Vec<u32>>,
+}
+
+/// The `AdapterDetails` dictionary, as negotiated over USB-C PD or MagSafe.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct AdapterDetails {
+    pub watts: Option<u32>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(deserialize_with = "signed")]
+    pub family_code: Option<i64>,
+    /// Millivolts
+    pub adapter_voltage: Option<u32>,
+    /// Milliamps
+    pub current: Option<u32>,
+    pub is_wireless: Option<bool>,
+}
+
+/// The `PowerTelemetryData` dictionary, only present on Apple silicon.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct PowerTelemetryData {
+    /// Milliwatts drawn by the whole system, from the adapter and battery
+    pub system_load: Option<u64>,
+}
+
+impl AppleSmartBattery {
+    /// Parse `ioreg -a -rc AppleSmartBattery` output. Empty output, or an
+    /// empty array, means the machine has no battery.
+    pub fn parse(stdout: &str) -> Result<Option<AppleSmartBattery>, MonitorError> {
+        if stdout.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let entries: Vec<AppleSmartBattery> = plist::from_bytes(stdout.as_bytes())
+            .map_err(|e| MonitorError::ParseError(format!("ioreg battery plist: {}", e)))?;
+        Ok(entries.into_iter().next())
+    }
+
+    pub fn reading(&self) -> BatteryReading {
+        let battery_data = self.battery_data.as_ref();
+
+        // Use AppleRaw* values for accurate readings (the non-raw ones are percentages)
+        BatteryReading {
+            current_capacity: self.raw_current_capacity.unwrap_or(0) as f32,
+            max_capacity: self.raw_max_capacity.unwrap_or(100) as f32,
+            design_capacity: self
+                .design_capacity
+                .or_else(|| battery_data.and_then(|data| data.design_capacity))
+                .unwrap_or(100) as f32,
+            is_charging: self.is_charging.unwrap_or(false),
+            external_connected: self.external_connected.unwrap_or(false),
+            cycle_count: self
+                .cycle_count
+                .or_else(|| battery_data.and_then(|data| data.cycle_count)),
+            temperature_celsius: self.temperature.map(|t| t as f32 / 100.0),
+            voltage_volts: self
+                .voltage
+                .or_else(|| battery_data.and_then(|data| data.voltage))
+                .map(|v| v as f32 / 1000.0),
+            time_remaining_minutes: self.time_remaining.filter(|&t| t < TIME_UNKNOWN),
+            time_to_full_minutes: self.avg_time_to_full.filter(|&t| t < TIME_UNKNOWN),
+            time_to_empty_minutes: self.avg_time_to_empty.filter(|&t| t < TIME_UNKNOWN),
+            // InstantAmperage tracks load changes, Amperage is averaged
+            amperage_ma: self.instant_amperage.or(self.amperage).map(|a| a as f32),
+            system_power_watts: self
+                .power_telemetry_data
+                .as_ref()
+                .and_then(|telemetry| telemetry.system_load)
+                .map(|mw| mw as f32 / 1000.0),
+            cell_voltages_mv: battery_data
+                .and_then(|data| data.cell_voltage.clone())
+                .unwrap_or_default(),
+            adapter: self
+                .adapter_details
+                .as_ref()
+                .filter(|_| self.external_connected == Some(true))
+                .map(|details| AdapterReading {
+                    watts: details.watts,
+                    name: details.name.clone(),
+                    description: details.description.clone(),
+                    family_code: details.family_code,
+                    voltage_mv: details.adapter_voltage,
+                    current_ma: details.current,
+                    is_wireless: details.is_wireless.unwrap_or(false),
+                }),
+            identity: BatteryIdentity {
+                serial: self.serial.clone(),
+                manufacturer: self.manufacturer.clone(),
+                device_name: self.device_name.clone(),
+                manufacture_date: self.manufacture_date.and_then(decode_manufacture_date),
+                design_capacity_mah: self
+                    .design_capacity
+                    .or_else(|| battery_data.and_then(|data| data.design_capacity)),
+                full_charge_capacity_mah: self.raw_max_capacity.or(self.nominal_charge_capacity),
+            },
+        }
+    }
+}
+
+/// Decode a Smart Battery date, packed as `(year - 1980) << 9 | month << 5 | day`.
+fn decode_manufacture_date(packed: u32) -> Option<ManufactureDate> {
+    ManufactureDate::new(
+        1980 + (packed >> 9) as u16,
+        ((packed >> 5) & 0xf) as u8,
+        (packed & 0x1f) as u8,
+    )
+}
+
+/// Signed integers, which ioreg writes as their unsigned two's complement
+/// (a discharge of 867 mA is `18446744073709550749`).
+fn signed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    struct SignedVisitor;
+
+    impl<'de> Visitor<'de> for SignedVisitor {
+        type Value = i64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an integer")
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<i64, E> {
+            Ok(v)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<i64, E> {
+            Ok(v as i64)
+        }
+    }
+
+    deserializer.deserialize_i64(SignedVisitor).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::testing::replay;
+    use crate::platform::CommandRunner;
+
+    fn fixture(scenario: &str) -> String {
+        replay(scenario)
+            .run("ioreg", &["-a", "-rc", "AppleSmartBattery"])
+            .unwrap()
+            .stdout
+    }
+
+    #[test]
+    fn test_parse_recorded_plist() {
+        let battery = AppleSmartBattery::parse(&fixture("macbook"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(battery.raw_current_capacity, Some(3897));
+        assert_eq!(battery.raw_max_capacity, Some(4382));
+        assert_eq!(battery.is_charging, Some(false));
+        assert_eq!(battery.amperage, Some(-867));
+        assert_eq!(battery.instant_amperage, Some(-914));
+
+        let data = battery.battery_data.unwrap();
+        assert_eq!(data.cell_voltage, Some(vec![4210, 4211, 4210]));
+        assert_eq!(data.design_capacity, Some(4563));
+    }
+
+    #[test]
+    fn test_reading_includes_power_telemetry() {
+        let reading = AppleSmartBattery::parse(&fixture("macbook"))
+            .unwrap()
+            .unwrap()
+            .reading();
+        assert_eq!(reading.amperage_ma, Some(-914.0));
+        assert_eq!(reading.system_power_watts, Some(11.546));
+        assert_eq!(reading.cell_voltages_mv, vec![4210, 4211, 4210]);
+    }
+
+    #[test]
+    fn test_adapter_only_reported_while_connected() {
+        let mut battery = AppleSmartBattery::parse(&fixture("charging"))
+            .unwrap()
+            .unwrap();
+        let adapter = battery.reading().adapter.unwrap();
+        assert_eq!(adapter.watts, Some(30));
+        assert_eq!(adapter.name.as_deref(), Some("30W USB-C Power Adapter"));
+        assert_eq!(adapter.family_code, Some(-536854518));
+        assert_eq!(adapter.voltage_mv, Some(15000));
+        assert_eq!(adapter.current_ma, Some(2000));
+
+        // macOS keeps the details of the last adapter after unplugging
+        battery.external_connected = Some(false);
+        assert!(battery.reading().adapter.is_none());
+    }
+
+    #[test]
+    fn test_identity() {
+        let identity = AppleSmartBattery::parse(&fixture("macbook"))
+            .unwrap()
+            .unwrap()
+            .reading()
+            .identity;
+        assert_eq!(identity.serial.as_deref(), Some("F8Y2147A1UPQ1CWAH"));
+        assert_eq!(identity.manufacturer.as_deref(), Some("SMP"));
+        assert_eq!(identity.device_name.as_deref(), Some("bq40z651"));
+        assert_eq!(identity.manufacture_date, ManufactureDate::new(2023, 1, 21));
+        assert_eq!(identity.design_capacity_mah, Some(4563));
+        assert_eq!(identity.full_charge_capacity_mah, Some(4382));
+    }
+
+    #[test]
+    fn test_decode_manufacture_date() {
+        assert_eq!(
+            decode_manufacture_date(22069),
+            ManufactureDate::new(2023, 1, 21)
+        );
+        // Month 0 is what an unprogrammed gauge reports
+        assert_eq!(decode_manufacture_date(0), None);
+    }
+
+    #[test]
+    fn test_nested_keys_do_not_shadow_top_level() {
+        // BatteryData carries its own Voltage and CycleCount
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><array><dict>
+  <key>BatteryData</key>
+  <dict><key>Voltage</key><integer>11000</integer><key>CycleCount</key><integer>3</integer></dict>
+  <key>Voltage</key><integer>12631</integer>
+</dict></array></plist>"#;
+        let mut battery = AppleSmartBattery::parse(plist).unwrap().unwrap();
+        assert_eq!(battery.reading().voltage_volts, Some(12.631));
+        // Only available from BatteryData here
+        assert_eq!(battery.reading().cycle_count, Some(3));
+
+        battery.voltage = None;
+        assert_eq!(battery.reading().voltage_volts, Some(11.0));
+    }
+
+    #[test]
+    fn test_no_battery() {
+        assert!(AppleSmartBattery::parse("").unwrap().is_none());
+        let empty = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><array/></plist>"#;
+        assert!(AppleSmartBattery::parse(empty).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_malformed_output_is_a_parse_error() {
+        let err = AppleSmartBattery::parse("+-o AppleSmartBattery  <class AppleSmartBattery>")
+            .unwrap_err();
+        assert_eq!(err.code(), "ParseError");
+    }
+
+    #[test]
+    fn test_unknown_time_remaining_is_dropped() {
+        let battery = AppleSmartBattery {
+            time_remaining: Some(TIME_UNKNOWN),
+            avg_time_to_full: Some(TIME_UNKNOWN),
+            avg_time_to_empty: Some(TIME_UNKNOWN),
+            ..Default::default()
+        };
+        let reading = battery.reading();
+        assert_eq!(reading.time_remaining_minutes, None);
+        assert_eq!(reading.time_to_full_minutes, None);
+        assert_eq!(reading.time_to_empty_minutes, None);
+    }
+}
@@ -0,0 +1,583 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+use sysinfo::MINIMUM_CPU_UPDATE_INTERVAL;
+
+use crate::error::MonitorError;
+
+use super::{
+    BatteryIdentity, BatteryReading, CpuBreakdownReading, CpuFrequencyReading, CpuUsageReading,
+    LoadReading, ManufactureDate, MemoryReading, SharedRunner, SystemCache, SystemProbe,
+};
+
+/// Probe for Linux hosts, reading `/proc` and `/sys` directly.
+///
+/// Process listings still come from `sysinfo`. The filesystem root is
+/// configurable so the parsers can be pointed at fixture trees in tests.
+pub struct LinuxProbe {
+    runner: SharedRunner,
+    root: PathBuf,
+    system_cache: SystemCache,
+    /// Previous `/proc/stat` sample, used to compute usage deltas
+    last_stat: Mutex<Option<(Instant, Vec<CpuTimes>)>>,
+}
+
+impl LinuxProbe {
+    pub fn new(runner: SharedRunner) -> Self {
+        Self::with_root(runner, "/")
+    }
+
+    pub fn with_root(runner: SharedRunner, root: impl Into<PathBuf>) -> Self {
+        Self {
+            runner,
+            root: root.into(),
+            system_cache: SystemCache::new(),
+            last_stat: Mutex::new(None),
+        }
+    }
+
+    fn path(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    fn last_stat(&self) -> MutexGuard<'_, Option<(Instant, Vec<CpuTimes>)>> {
+        match self.last_stat.lock() {
+            Ok(last_stat) => last_stat,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn read_stat(&self) -> Option<Vec<CpuTimes>> {
+        let content = fs::read_to_string(self.path("proc/stat")).ok()?;
+        let times = parse_proc_stat(&content);
+        if times.is_empty() {
+            None
+        } else {
+            Some(times)
+        }
+    }
+}
+
+impl SystemProbe for LinuxProbe {
+    fn system_cache(&self) -> &SystemCache {
+        &self.system_cache
+    }
+
+    fn memory(&self) -> Result<MemoryReading, MonitorError> {
+        let content = fs::read_to_string(self.path("proc/meminfo"))?;
+        parse_meminfo(&content).ok_or_else(|| MonitorError::ParseError("/proc/meminfo".to_string()))
+    }
+
+    fn cpu_usage(&self) -> Option<CpuUsageReading> {
+        // Take a priming sample on first use, and make sure the delta spans
+        // at least the minimum interval so it isn't dominated by noise. The
+        // lock is only held to swap samples, not while waiting.
+        let last_stat = self.last_stat().take();
+        let (taken_at, previous) = match last_stat {
+            Some(sample) => sample,
+            None => (Instant::now(), self.read_stat()?),
+        };
+        if let Some(remaining) = MINIMUM_CPU_UPDATE_INTERVAL.checked_sub(taken_at.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+
+        let current = self.read_stat()?;
+        let reading = usage_between(&previous, &current);
+        *self.last_stat() = Some((Instant::now(), current));
+        reading
+    }
+
+    fn cpu_frequency(&self) -> CpuFrequencyReading {
+        // Offline cores keep their directory, and the numbering can have
+        // gaps, so list what's there rather than counting up from cpu0
+        let cpu_dir = self.path("sys/devices/system/cpu");
+        let mut cores: Vec<usize> = fs::read_dir(&cpu_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| {
+                        let name = entry.ok()?.file_name();
+                        name.to_str()?.strip_prefix("cpu")?.parse().ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        cores.sort_unstable();
+
+        // cpufreq values are in kHz; cores without a driver have no directory
+        let mut per_core_khz = Vec::new();
+        let mut max_khz = None;
+        let mut nominal_khz = None;
+        for core in cores {
+            let core_dir = cpu_dir.join(format!("cpu{}", core));
+            // Left out of /proc/stat as well, so the indices stay in step
+            // with the per-core usage. cpu0 usually has no `online` file.
+            if read_sysfs(&core_dir, "online").as_deref() == Some("0") {
+                continue;
+            }
+            let cpufreq = core_dir.join("cpufreq");
+            per_core_khz.push(read_sysfs_u64(&cpufreq, "scaling_cur_freq"));
+            max_khz = max_khz.max(read_sysfs_u64(&cpufreq, "cpuinfo_max_freq"));
+            // Only intel_pstate reports a base frequency
+            if nominal_khz.is_none() {
+                nominal_khz = read_sysfs_u64(&cpufreq, "base_frequency");
+            }
+        }
+
+        let to_mhz = |khz: Option<u64>| khz.filter(|&khz| khz > 0).map(|khz| khz / 1000);
+        CpuFrequencyReading {
+            per_core_mhz: per_core_khz.into_iter().map(to_mhz).collect(),
+            nominal_mhz: to_mhz(nominal_khz),
+            max_mhz: to_mhz(max_khz),
+        }
+    }
+
+    fn load_average(&self) -> LoadReading {
+        fs::read_to_string(self.path("proc/loadavg"))
+            .ok()
+            .and_then(|content| parse_loadavg(&content))
+            .unwrap_or_default()
+    }
+
+    fn battery(&self) -> Result<Option<BatteryReading>, MonitorError> {
+        let supplies = match fs::read_dir(self.path("sys/class/power_supply")) {
+            Ok(entries) => entries,
+            // No power supply class at all (containers, some VMs)
+            Err(_) => return Ok(None),
+        };
+
+        let mut battery_dir = None;
+        let mut external_connected = false;
+        let mut supply_dirs: Vec<PathBuf> =
+            supplies.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        supply_dirs.sort();
+
+        for dir in supply_dirs {
+            let name = dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            match read_sysfs(&dir, "type").as_deref() {
+                Some("Battery") if name.starts_with("BAT") && battery_dir.is_none() => {
+                    battery_dir = Some(dir);
+                }
+                Some("Mains") | Some("USB")
+                    if read_sysfs(&dir, "online").as_deref() == Some("1") =>
+                {
+                    external_connected = true;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(battery_dir.map(|dir| read_sysfs_battery(&dir, external_connected)))
+    }
+
+    fn dir_size(&self, path: &Path) -> u64 {
+        super::du_dir_size(&*self.runner, path)
+    }
+}
+
+/// Cumulative jiffies for one line of `/proc/stat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct CpuTimes {
+    /// The core number, `None` for the aggregate `cpu` line
+    cpu: Option<usize>,
+    busy: u64,
+    total: u64,
+    /// user nice system idle iowait irq softirq steal, 0 where not reported
+    states: [u64; 8],
+}
+
+/// Parse the `cpu` lines of `/proc/stat`: the aggregate, then one per online
+/// core. Offline cores are left out, so the numbers can have gaps.
+fn parse_proc_stat(content: &str) -> Vec<CpuTimes> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let cpu = match fields.next()?.strip_prefix("cpu")? {
+                "" => None,
+                number => Some(number.parse::<usize>().ok()?),
+            };
+            let values: Vec<u64> = fields.filter_map(|v| v.parse::<u64>().ok()).collect();
+            // user nice system idle iowait irq softirq steal [guest guest_nice]
+            if values.len() < 4 {
+                return None;
+            }
+            // Guest time is already included in user/nice
+            let total: u64 = values.iter().take(8).sum();
+            let idle = values[3] + values.get(4).copied().unwrap_or(0);
+            let mut states = [0; 8];
+            for (state, value) in states.iter_mut().zip(&values) {
+                *state = *value;
+            }
+            Some(CpuTimes {
+                cpu,
+                busy: total.saturating_sub(idle),
+                total,
+                states,
+            })
+        })
+        .collect()
+}
+
+fn usage_percentage(previous: CpuTimes, current: CpuTimes) -> f32 {
+    let total = current.total.saturating_sub(previous.total);
+    if total == 0 {
+        return 0.0;
+    }
+    let busy = current.busy.saturating_sub(previous.busy);
+    ((busy as f32 / total as f32) * 100.0).clamp(0.0, 100.0)
+}
+
+/// How the time between two samples was spent; `None` without a delta.
+fn breakdown_between(previous: CpuTimes, current: CpuTimes) -> Option<CpuBreakdownReading> {
+    let total = current.total.saturating_sub(previous.total);
+    if total == 0 {
+        return None;
+    }
+    let share = |states: &[usize]| {
+        let ticks: u64 = states
+            .iter()
+            .map(|&state| current.states[state].saturating_sub(previous.states[state]))
+            .sum();
+        (ticks * 100) as f32 / total as f32
+    };
+    Some(CpuBreakdownReading {
+        user: share(&[0]),
+        // Interrupt handling is kernel time too
+        system: share(&[2, 5, 6]),
+        idle: share(&[3]),
+        nice: Some(share(&[1])),
+        iowait: Some(share(&[4])),
+        steal: Some(share(&[7])),
+    })
+}
+
+fn usage_between(previous: &[CpuTimes], current: &[CpuTimes]) -> Option<CpuUsageReading> {
+    let times_of =
+        |times: &[CpuTimes], cpu: Option<usize>| times.iter().find(|t| t.cpu == cpu).copied();
+    let (all_previous, all_current) = (times_of(previous, None)?, times_of(current, None)?);
+    let total_percentage = usage_percentage(all_previous, all_current);
+
+    // Matched by number, as a core going offline between the samples
+    // shifts the ones after it. One that just came online is measured
+    // since boot.
+    let per_core = current
+        .iter()
+        .filter(|cur| cur.cpu.is_some())
+        .map(|cur| {
+            let prev = times_of(previous, cur.cpu).unwrap_or_default();
+            usage_percentage(prev, *cur)
+        })
+        .collect();
+    Some(CpuUsageReading {
+        total_percentage,
+        per_core,
+        breakdown: breakdown_between(all_previous, all_current),
+    })
+}
+
+/// Parse `/proc/meminfo`; used memory excludes reclaimable caches.
+fn parse_meminfo(content: &str) -> Option<MemoryReading> {
+    let field = |key: &str| -> Option<u64> {
+        content
+            .lines()
+            .find(|line| line.starts_with(key) && line[key.len()..].starts_with(':'))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+    };
+
+    let total_bytes = field("MemTotal")?;
+    // MemAvailable appeared in Linux 3.14; approximate it on older kernels
+    let available = field("MemAvailable").or_else(|| {
+        Some(field("MemFree")? + field("Buffers").unwrap_or(0) + field("Cached").unwrap_or(0))
+    })?;
+
+    Some(MemoryReading {
+        total_bytes,
+        used_bytes: total_bytes.saturating_sub(available),
+    })
+}
+
+/// Parse "0.52 0.58 0.59 1/467 12345" from `/proc/loadavg`.
+fn parse_loadavg(content: &str) -> Option<LoadReading> {
+    let mut values = content.split_whitespace().map(|v| v.parse::<f64>().ok());
+    Some(LoadReading {
+        one: values.next()??,
+        five: values.next()??,
+        fifteen: values.next()??,
+    })
+}
+
+fn read_sysfs(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|v| v.trim().to_string())
+}
+
+fn read_sysfs_number(dir: &Path, file: &str) -> Option<f32> {
+    read_sysfs(dir, file)?.parse::<f32>().ok()
+}
+
+fn read_sysfs_u64(dir: &Path, file: &str) -> Option<u64> {
+    read_sysfs(dir, file)?.parse::<u64>().ok()
+}
+
+/// Read a `/sys/class/power_supply/BAT*` directory.
+///
+/// Batteries report either energy (µWh) or charge (µAh) counters; only the
+/// ratios and the rate matter here, so both are used as-is.
+fn read_sysfs_battery(dir: &Path, external_connected: bool) -> BatteryReading {
+    let (prefix, rate_file) = if dir.join("energy_now").exists() {
+        ("energy", "power_now")
+    } else {
+        ("charge", "current_now")
+    };
+    let now = read_sysfs_number(dir, &format!("{}_now", prefix));
+    let full = read_sysfs_number(dir, &format!("{}_full", prefix));
+    let design = read_sysfs_number(dir, &format!("{}_full_design", prefix));
+    let rate = read_sysfs_number(dir, rate_file).filter(|&r| r > 0.0);
+
+    let status = read_sysfs(dir, "status").unwrap_or_default();
+    let is_charging = status == "Charging";
+    let is_discharging = status == "Discharging";
+    let voltage_volts = read_sysfs_number(dir, "voltage_now").map(|v| v / 1_000_000.0); // µV
+
+    // Fall back to the capacity percentage when the counters are missing
+    let (current_capacity, max_capacity) = match (now, full) {
+        (Some(now), Some(full)) => (now, full),
+        _ => (read_sysfs_number(dir, "capacity").unwrap_or(0.0), 100.0),
+    };
+
+    let time_remaining_minutes = match (now, full, rate) {
+        (Some(now), _, Some(rate)) if is_discharging => Some(now / rate * 60.0),
+        (Some(now), Some(full), Some(rate)) if is_charging => {
+            Some((full - now).max(0.0) / rate * 60.0)
+        }
+        _ => None,
+    }
+    .map(|minutes| minutes.round() as u32);
+
+    // current_now is in µA, power_now in µW; both are unsigned in most drivers
+    let power_watts = read_sysfs_number(dir, "power_now").map(|p| p / 1_000_000.0);
+    let amperage_ma = read_sysfs_number(dir, "current_now")
+        .map(|current| current / 1000.0)
+        .or_else(|| match (power_watts, voltage_volts) {
+            (Some(power), Some(voltage)) if voltage > 0.0 => Some(power / voltage * 1000.0),
+            _ => None,
+        })
+        .map(|current| {
+            if is_discharging {
+                -current.abs()
+            } else {
+                current.abs()
+            }
+        });
+
+    BatteryReading {
+        current_capacity,
+        max_capacity,
+        design_capacity: design.unwrap_or(max_capacity),
+        is_charging,
+        external_connected: external_connected || is_charging || status == "Full",
+        cycle_count: read_sysfs(dir, "cycle_count")
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|&c| c > 0), // Many drivers report 0 when unsupported
+        temperature_celsius: read_sysfs_number(dir, "temp").map(|t| t / 10.0), // Tenths of a degree
+        voltage_volts,
+        time_remaining_minutes,
+        time_to_full_minutes: time_remaining_minutes.filter(|_| is_charging),
+        time_to_empty_minutes: time_remaining_minutes.filter(|_| is_discharging),
+        amperage_ma,
+        // On battery the pack supplies everything the system draws
+        system_power_watts: power_watts.filter(|_| is_discharging),
+        cell_voltages_mv: Vec::new(),
+        adapter: None,
+        identity: BatteryIdentity {
+            serial: read_sysfs(dir, "serial_number"),
+            manufacturer: read_sysfs(dir, "manufacturer"),
+            device_name: read_sysfs(dir, "model_name"),
+            manufacture_date: match (
+                read_sysfs(dir, "manufacture_year").and_then(|v| v.parse().ok()),
+                read_sysfs(dir, "manufacture_month").and_then(|v| v.parse().ok()),
+                read_sysfs(dir, "manufacture_day").and_then(|v| v.parse().ok()),
+            ) {
+                (Some(year), Some(month), Some(day)) => ManufactureDate::new(year, month, day),
+                _ => None,
+            },
+            // Only charge counters are in µAh
+            design_capacity_mah: design
+                .filter(|_| prefix == "charge")
+                .map(|uah| (uah / 1000.0).round() as u32),
+            full_charge_capacity_mah: full
+                .filter(|_| prefix == "charge")
+                .map(|uah| (uah / 1000.0).round() as u32),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::SystemRunner;
+    use std::sync::Arc;
+
+    fn fixture(name: &str) -> LinuxProbe {
+        LinuxProbe::with_root(
+            Arc::new(SystemRunner),
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/linux")
+                .join(name),
+        )
+    }
+
+    #[test]
+    fn test_memory_from_meminfo() {
+        let memory = fixture("laptop").memory().unwrap();
+        assert_eq!(memory.total_bytes, 16_303_040 * 1024);
+        assert_eq!(memory.used_bytes, (16_303_040 - 9_864_112) * 1024);
+    }
+
+    #[test]
+    fn test_parse_meminfo_without_mem_available() {
+        let content = "MemTotal: 1000 kB\nMemFree: 200 kB\nBuffers: 100 kB\nCached: 300 kB\n";
+        let memory = parse_meminfo(content).unwrap();
+        assert_eq!(memory.used_bytes, 400 * 1024);
+    }
+
+    #[test]
+    fn test_load_average_from_loadavg() {
+        let load = fixture("laptop").load_average();
+        assert_eq!(load.one, 0.52);
+        assert_eq!(load.five, 0.58);
+        assert_eq!(load.fifteen, 0.61);
+    }
+
+    #[test]
+    fn test_usage_between_stat_samples() {
+        let previous = parse_proc_stat(
+            "cpu  100 0 100 800 0 0 0 0 0 0\ncpu0 50 0 50 400 0 0 0 0 0 0\ncpu1 50 0 50 400 0 0 0 0 0 0\n",
+        );
+        let current = parse_proc_stat(
+            "cpu  200 0 150 850 0 0 0 0 0 0\ncpu0 140 0 60 400 0 0 0 0 0 0\ncpu1 60 0 90 450 0 0 0 0 0 0\n",
+        );
+
+        let usage = usage_between(&previous, &current).unwrap();
+        assert_eq!(usage.total_percentage, 75.0);
+        assert_eq!(usage.per_core, vec![100.0, 50.0]);
+        let breakdown = usage.breakdown.unwrap();
+        assert_eq!(breakdown.user, 50.0);
+        assert_eq!(breakdown.system, 25.0);
+        assert_eq!(breakdown.idle, 25.0);
+        assert_eq!(breakdown.nice, Some(0.0));
+
+        let previous = parse_proc_stat("cpu  100 20 100 700 40 5 5 30 0 0\n");
+        let current = parse_proc_stat("cpu  140 30 120 820 80 10 10 40 0 0\n");
+        let breakdown = usage_between(&previous, &current)
+            .unwrap()
+            .breakdown
+            .unwrap();
+        assert_eq!(breakdown.user, 16.0);
+        assert_eq!(breakdown.nice, Some(4.0));
+        assert_eq!(breakdown.system, 12.0);
+        assert_eq!(breakdown.idle, 48.0);
+        assert_eq!(breakdown.iowait, Some(16.0));
+        assert_eq!(breakdown.steal, Some(4.0));
+    }
+
+    #[test]
+    fn test_cpu_usage_from_proc_stat() {
+        let usage = fixture("laptop").cpu_usage().unwrap();
+        // The fixture doesn't change between samples, so there is no delta
+        assert_eq!(usage.total_percentage, 0.0);
+        assert_eq!(usage.per_core.len(), 4);
+        assert!(usage.breakdown.is_none());
+    }
+
+    #[test]
+    fn test_cpu_usage_survives_a_poisoned_lock() {
+        let probe = Arc::new(fixture("laptop"));
+        let poisoner = Arc::clone(&probe);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.last_stat.lock().unwrap();
+            panic!("poison the previous sample");
+        })
+        .join();
+        assert!(probe.last_stat.is_poisoned());
+
+        assert!(probe.cpu_usage().is_some());
+        assert!(probe.cpu_usage().is_some());
+    }
+
+    #[test]
+    fn test_offline_cores_are_matched_by_number() {
+        let previous = parse_proc_stat(
+            "cpu  200 0 0 200 0 0 0 0 0 0\ncpu0 100 0 0 100 0 0 0 0 0 0\ncpu2 100 0 0 100 0 0 0 0 0 0\n",
+        );
+        let current = parse_proc_stat(
+            "cpu  300 0 0 300 0 0 0 0 0 0\ncpu0 100 0 0 200 0 0 0 0 0 0\ncpu1 50 0 0 50 0 0 0 0 0 0\ncpu2 200 0 0 100 0 0 0 0 0 0\n",
+        );
+        assert_eq!(current[2].cpu, Some(1));
+
+        let usage = usage_between(&previous, &current).unwrap();
+        // cpu1 came back online, so its share is since boot
+        assert_eq!(usage.per_core, vec![0.0, 50.0, 100.0]);
+
+        let probe = fixture("offline");
+        assert_eq!(probe.cpu_usage().unwrap().per_core.len(), 3);
+        assert_eq!(
+            probe.cpu_frequency().per_core_mhz,
+            vec![Some(2400), Some(2600), Some(3000)]
+        );
+    }
+
+    #[test]
+    fn test_cpu_frequency_from_cpufreq() {
+        let frequency = fixture("laptop").cpu_frequency();
+        assert_eq!(
+            frequency.per_core_mhz,
+            vec![Some(3412), Some(1200), Some(4001), None]
+        );
+        assert_eq!(frequency.nominal_mhz, Some(2800));
+        assert_eq!(frequency.max_mhz, Some(4700));
+
+        // No cpufreq at all, as in most VMs
+        let frequency = fixture("desktop").cpu_frequency();
+        assert!(frequency.per_core_mhz.is_empty());
+        assert_eq!(frequency.max_mhz, None);
+    }
+
+    #[test]
+    fn test_battery_from_sysfs() {
+        let reading = fixture("laptop").battery().unwrap().unwrap();
+        assert_eq!(reading.current_capacity, 30_000_000.0);
+        assert_eq!(reading.max_capacity, 50_000_000.0);
+        assert_eq!(reading.design_capacity, 57_000_000.0);
+        assert!(!reading.is_charging);
+        assert!(!reading.external_connected);
+        assert_eq!(reading.cycle_count, Some(412));
+        assert_eq!(reading.voltage_volts, Some(11.9));
+        // 30 Wh at 10 W
+        assert_eq!(reading.time_remaining_minutes, Some(180));
+        assert_eq!(reading.system_power_watts, Some(10.0));
+        // 10 W at 11.9 V, discharging
+        assert!((reading.amperage_ma.unwrap() + 840.34).abs() < 0.01);
+
+        let identity = reading.identity;
+        assert_eq!(identity.serial.as_deref(), Some("41167"));
+        assert_eq!(identity.manufacturer.as_deref(), Some("SMP"));
+        assert_eq!(identity.device_name.as_deref(), Some("5B10W13975"));
+        assert_eq!(identity.manufacture_date, ManufactureDate::new(2021, 3, 9));
+        // Energy counters, so no mAh figures
+        assert_eq!(identity.design_capacity_mah, None);
+    }
+
+    #[test]
+    fn test_desktop_has_no_battery() {
+        let probe = fixture("desktop");
+        assert!(probe.battery().unwrap().is_none());
+    }
+}
@@ -0,0 +1,543 @@
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::error::MonitorError;
+
+use super::ioreg::AppleSmartBattery;
+use super::pmset::{
+    parse_assertions, parse_low_power_mode, parse_power_sources, parse_sleep_log, parse_thermal,
+};
+use super::sysctl::{parse_cpu_frequency, parse_cpu_topology};
+use super::{
+    run_tool, AssertionReading, BatteryReading, CpuBreakdownReading, CpuFrequencyReading,
+    CpuTopologyReading, CpuUsageReading, PowerSourcesReading, ProcessCpuReading,
+    ProcessMemoryReading, SharedRunner, SleepEventReading, SystemCache, SystemProbe,
+    ThermalReading,
+};
+
+/// Probe backed by the macOS command line tools (`ioreg`, `pmset`, `top`, `ps`,
+/// `du`).
+pub struct MacProbe {
+    runner: SharedRunner,
+    system_cache: SystemCache,
+    /// Nothing in `sysctl hw` changes while the app runs, so it's only run
+    /// the first time it's needed
+    hardware: OnceLock<Hardware>,
+    /// Last thermal reading and when it was taken
+    thermal: Mutex<Option<(Instant, Option<ThermalReading>)>>,
+}
+
+/// How long a `pmset -g therm` reading is reused. Thermal pressure builds
+/// up over minutes, so there's no need to fork pmset on every CPU sample.
+const THERMAL_MAX_AGE: Duration = Duration::from_secs(30);
+
+/// What the probe reads from `sysctl hw`.
+struct Hardware {
+    topology: CpuTopologyReading,
+    frequency: CpuFrequencyReading,
+}
+
+impl MacProbe {
+    pub fn new(runner: SharedRunner) -> Self {
+        Self {
+            runner,
+            system_cache: SystemCache::new(),
+            hardware: OnceLock::new(),
+            thermal: Mutex::new(None),
+        }
+    }
+
+    fn hardware(&self) -> &Hardware {
+        self.hardware.get_or_init(|| {
+            let (topology, mut frequency) = match run_tool(&*self.runner, "sysctl", &["hw"]) {
+                Ok(stdout) => (parse_cpu_topology(&stdout), parse_cpu_frequency(&stdout)),
+                Err(_) => (self.system_cache.cpu_topology(), Default::default()),
+            };
+            // sysinfo reports the rated maximum on macOS, not the current clock
+            if frequency.max_mhz.is_none() {
+                frequency.max_mhz = self
+                    .system_cache
+                    .per_core_frequency()
+                    .into_iter()
+                    .flatten()
+                    .max();
+            }
+            Hardware {
+                topology,
+                frequency,
+            }
+        })
+    }
+}
+
+impl SystemProbe for MacProbe {
+    fn system_cache(&self) -> &SystemCache {
+        &self.system_cache
+    }
+
+    fn memory_processes(&self) -> Result<Vec<ProcessMemoryReading>, MonitorError> {
+        // Use ps command - much faster than sysinfo for process listing
+        let stdout = run_tool(&*self.runner, "ps", &["-axm", "-o", "pid,rss,command"])?;
+        Ok(parse_ps_memory(&stdout))
+    }
+
+    fn cpu_processes(&self) -> Result<Vec<ProcessCpuReading>, MonitorError> {
+        // -r sorts by CPU usage descending
+        let stdout = run_tool(&*self.runner, "ps", &["-arcwwwxo", "pid,%cpu,comm"])?;
+        Ok(parse_ps_cpu(&stdout))
+    }
+
+    fn cpu_topology(&self) -> CpuTopologyReading {
+        self.hardware().topology.clone()
+    }
+
+    /// macOS has no current clock to offer, on Apple Silicon not even per
+    /// performance level, so this is only ever the rated speeds.
+    fn cpu_frequency(&self) -> CpuFrequencyReading {
+        self.hardware().frequency.clone()
+    }
+
+    fn cpu_usage(&self) -> Option<CpuUsageReading> {
+        let per_core = self.system_cache.per_core_usage();
+
+        // top is more reliable than sysinfo for the overall figure on macOS,
+        // the per-core average is the next best measurement
+        let breakdown = self
+            .runner
+            .run("top", &["-l", "1", "-n", "0"])
+            .ok()
+            .and_then(|output| parse_top_cpu_usage(&output.stdout));
+        let total_percentage = match &breakdown {
+            Some(breakdown) => (100.0 - breakdown.idle).clamp(0.0, 100.0),
+            None if per_core.is_empty() => return None,
+            None => per_core.iter().sum::<f32>() / per_core.len() as f32,
+        };
+
+        Some(CpuUsageReading {
+            total_percentage,
+            per_core,
+            breakdown,
+        })
+    }
+
+    fn battery(&self) -> Result<Option<BatteryReading>, MonitorError> {
+        // Use ioreg to get accurate battery info from macOS
+        let stdout = run_tool(&*self.runner, "ioreg", &["-a", "-rc", "AppleSmartBattery"])?;
+        Ok(AppleSmartBattery::parse(&stdout)?.map(|battery| battery.reading()))
+    }
+
+    fn low_power_mode(&self) -> Option<bool> {
+        let stdout = run_tool(&*self.runner, "pmset", &["-g"]).ok()?;
+        parse_low_power_mode(&stdout)
+    }
+
+    fn power_sources(&self) -> Result<Option<PowerSourcesReading>, MonitorError> {
+        // `-g batt` prints the same listing on releases without `-g ps`
+        let stdout = run_tool(&*self.runner, "pmset", &["-g", "ps"])
+            .or_else(|_| run_tool(&*self.runner, "pmset", &["-g", "batt"]))?;
+        parse_power_sources(&stdout).map(Some)
+    }
+
+    fn sleep_log(&self) -> Result<Vec<SleepEventReading>, MonitorError> {
+        let stdout = run_tool(&*self.runner, "pmset", &["-g", "log"])?;
+        Ok(parse_sleep_log(&stdout))
+    }
+
+    fn assertions(&self) -> Result<Vec<AssertionReading>, MonitorError> {
+        let stdout = run_tool(&*self.runner, "pmset", &["-g", "assertions"])?;
+        Ok(parse_assertions(&stdout))
+    }
+
+    fn thermal(&self) -> Option<ThermalReading> {
+        let mut cached = match self.thermal.lock() {
+            Ok(cached) => cached,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some((taken_at, reading)) = cached.as_ref() {
+            if taken_at.elapsed() < THERMAL_MAX_AGE {
+                return reading.clone();
+            }
+        }
+
+        let reading = run_tool(&*self.runner, "pmset", &["-g", "therm"])
+            .ok()
+            .map(|stdout| parse_thermal(&stdout));
+        *cached = Some((Instant::now(), reading.clone()));
+        reading
+    }
+
+    fn dir_size(&self, path: &Path) -> u64 {
+        super::du_dir_size(&*self.runner, path)
+    }
+}
+
+/// Parse `ps -axm -o pid,rss,command` output (RSS is in KB).
+fn parse_ps_memory(stdout: &str) -> Vec<ProcessMemoryReading> {
+    stdout
+        .lines()
+        .skip(1) // Skip header
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 3 {
+                let pid = parts[0].parse::<u32>().ok()?;
+                let rss_kb = parts[1].parse::<u64>().ok()?;
+                Some(ProcessMemoryReading {
+                    pid,
+                    command: parts[2..].join(" "),
+                    rss_bytes: rss_kb * 1024,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parse `ps -arcwwwxo pid,%cpu,comm` output, keeping ps' ordering.
+fn parse_ps_cpu(stdout: &str) -> Vec<ProcessCpuReading> {
+    stdout
+        .lines()
+        .skip(1) // Skip header
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 3 {
+                let pid = parts[0].parse::<u32>().ok()?;
+                let cpu_percentage = parts[1].parse::<f32>().ok()?;
+                Some(ProcessCpuReading {
+                    pid,
+                    name: parts[2..].join(" "),
+                    cpu_percentage,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parse "CPU usage: 26.85% user, 19.57% sys, 53.57% idle" into user + sys.
+///
+/// The labels are translated when top runs under a non-English locale
+/// ("CPU-Auslastung: 5,12% Benutzer, ..."), so the line is matched on "CPU"
+/// and its three percentages, and decimal commas are accepted.
+fn parse_top_cpu_usage(stdout: &str) -> Option<CpuBreakdownReading> {
+    stdout
+        .lines()
+        .filter(|line| line.contains("CPU"))
+        .find_map(|line| {
+            // user, sys, idle, in that order whatever the language
+            match parse_percentages(line).as_slice() {
+                [user, system, idle, ..] => Some(CpuBreakdownReading {
+                    user: *user,
+                    system: *system,
+                    idle: *idle,
+                    ..Default::default()
+                }),
+                _ => None,
+            }
+        })
+}
+
+/// Every number directly followed by a `%` sign (optionally after a space).
+fn parse_percentages(line: &str) -> Vec<f32> {
+    let chunks: Vec<&str> = line.split('%').collect();
+    // The text after the last sign isn't followed by one
+    chunks[..chunks.len().saturating_sub(1)]
+        .iter()
+        .filter_map(|chunk| {
+            chunk
+                .trim_end()
+                .rsplit(|c: char| c.is_whitespace() || c == ':')
+                .next()
+                .and_then(|number| number.replace(',', ".").parse::<f32>().ok())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::battery::BatteryHistory;
+    use crate::monitors::{battery, cpu, power, ram, sleep};
+    use crate::platform::testing;
+    use crate::platform::{CommandOutput, CommandRunner, ReplayRunner};
+    use std::sync::{Arc, Mutex};
+
+    fn replay(scenario: &str) -> MacProbe {
+        MacProbe::new(Arc::new(testing::replay(scenario)))
+    }
+
+    /// Replays a scenario and remembers which programs were run.
+    struct CountingRunner {
+        replay: ReplayRunner,
+        programs: Mutex<Vec<String>>,
+    }
+
+    impl CountingRunner {
+        fn runs(&self, program: &str) -> usize {
+            let programs = self.programs.lock().unwrap();
+            programs.iter().filter(|p| *p == program).count()
+        }
+    }
+
+    impl CommandRunner for CountingRunner {
+        fn run(&self, program: &str, args: &[&str]) -> std::io::Result<CommandOutput> {
+            self.programs.lock().unwrap().push(program.to_string());
+            self.replay.run(program, args)
+        }
+    }
+
+    fn counting(scenario: &str) -> (MacProbe, Arc<CountingRunner>) {
+        let runner = Arc::new(CountingRunner {
+            replay: testing::replay(scenario),
+            programs: Mutex::new(Vec::new()),
+        });
+        (MacProbe::new(runner.clone()), runner)
+    }
+
+    #[test]
+    fn test_parse_top_cpu_usage() {
+        let stdout = "Processes: 512 total\nCPU usage: 26.85% user, 19.57% sys, 53.57% idle\n";
+        let usage = parse_top_cpu_usage(stdout).unwrap();
+        assert_eq!(usage.user, 26.85);
+        assert_eq!(usage.system, 19.57);
+        assert_eq!(usage.idle, 53.57);
+        assert_eq!(usage.nice, None);
+        assert_eq!(parse_top_cpu_usage("Processes: 512 total\n"), None);
+    }
+
+    #[test]
+    fn test_parse_top_cpu_usage_localized() {
+        let german = "CPU-Auslastung: 5,12% Benutzer, 3,40% System, 91,48% inaktiv";
+        let usage = parse_top_cpu_usage(german).unwrap();
+        assert_eq!((usage.user, usage.system, usage.idle), (5.12, 3.40, 91.48));
+
+        let french = "Utilisation CPU : 12,5 % utilisateur, 7,5 % système, 80,0 % inactif";
+        let usage = parse_top_cpu_usage(french).unwrap();
+        assert_eq!((usage.user, usage.system, usage.idle), (12.5, 7.5, 80.0));
+    }
+
+    #[test]
+    fn test_cpu_usage_from_localized_top() {
+        let usage = replay("localized").cpu_usage().unwrap();
+        assert!((usage.total_percentage - 8.52).abs() < 0.01);
+        assert!(!usage.per_core.is_empty());
+        assert_eq!(usage.breakdown.unwrap().user, 5.12);
+    }
+
+    #[test]
+    fn test_cpu_usage_without_top_averages_cores() {
+        let usage = replay("restricted").cpu_usage().unwrap();
+        let average = usage.per_core.iter().sum::<f32>() / usage.per_core.len() as f32;
+        assert!((usage.total_percentage - average).abs() < 0.01);
+        assert!(usage.breakdown.is_none());
+    }
+
+    #[test]
+    fn test_sysctl_is_read_once() {
+        let (probe, runner) = counting("macbook");
+        assert_eq!(probe.cpu_topology().perf_levels.len(), 2);
+        assert!(probe.cpu_frequency().per_core_mhz.is_empty());
+        assert_eq!(probe.cpu_topology().perf_levels.len(), 2);
+        assert_eq!(runner.runs("sysctl"), 1);
+
+        // Without sysctl the sysinfo layout is kept instead
+        let (probe, runner) = counting("restricted");
+        assert!(probe.cpu_topology().perf_levels.is_empty());
+        probe.cpu_topology();
+        assert_eq!(runner.runs("sysctl"), 1);
+    }
+
+    #[test]
+    fn test_thermal_reading_is_reused() {
+        let (probe, runner) = counting("desktop");
+        assert_eq!(probe.thermal().unwrap().cpu_speed_limit, Some(78));
+        assert_eq!(probe.thermal().unwrap().cpu_speed_limit, Some(78));
+        assert_eq!(runner.runs("pmset"), 1);
+    }
+
+    #[test]
+    fn test_memory_processes_from_ps() {
+        let processes = replay("macbook").memory_processes().unwrap();
+        assert_eq!(processes.len(), 5);
+        assert_eq!(processes[0].pid, 1);
+        assert_eq!(processes[0].command, "/sbin/launchd");
+        assert_eq!(processes[2].rss_bytes, 1_254_112 * 1024);
+        assert_eq!(
+            processes[2].command,
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome"
+        );
+    }
+
+    #[test]
+    fn test_truncated_ps_lines_are_skipped() {
+        let processes = replay("truncated").memory_processes().unwrap();
+        let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![1, 88]);
+
+        let processes = replay("truncated").cpu_processes().unwrap();
+        let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![412]);
+    }
+
+    #[test]
+    fn test_cpu_processes_keep_ps_order() {
+        let processes = replay("macbook").cpu_processes().unwrap();
+        assert_eq!(processes[0].name, "WindowServer");
+        assert_eq!(processes[1].name, "Google Chrome Helper");
+        assert_eq!(processes[1].cpu_percentage, 12.4);
+    }
+
+    #[test]
+    fn test_failing_ps_is_an_error() {
+        let err = replay("restricted").memory_processes().unwrap_err();
+        assert_eq!(
+            err,
+            MonitorError::PermissionDenied("ps: Operation not permitted".to_string())
+        );
+    }
+
+    #[test]
+    fn test_battery_from_ioreg() {
+        let reading = replay("macbook").battery().unwrap().unwrap();
+        assert_eq!(reading.current_capacity, 3_897.0);
+        assert_eq!(reading.max_capacity, 4_382.0);
+        assert_eq!(reading.design_capacity, 4_563.0);
+        assert!(!reading.is_charging);
+        assert!(!reading.external_connected);
+        assert_eq!(reading.cycle_count, Some(187));
+        assert_eq!(reading.temperature_celsius, Some(30.52));
+        assert_eq!(reading.voltage_volts, Some(12.631));
+        assert_eq!(reading.time_remaining_minutes, Some(412));
+    }
+
+    #[test]
+    fn test_power_sources_from_pmset() {
+        let info = power::power_source_info(&replay("macbook")).unwrap();
+        assert_eq!(info.source, power::PowerSource::InternalBattery);
+        assert!(info.has_internal_battery);
+        assert!(info.ups.is_none());
+
+        let info = power::power_source_info(&replay("desktop")).unwrap();
+        assert_eq!(info.source, power::PowerSource::Ups);
+        assert!(!info.has_internal_battery);
+        let ups = info.ups.unwrap();
+        assert_eq!(ups.name, "Back-UPS ES 700G FW:871.O2 .I USB FW:O2");
+        assert_eq!(ups.percentage, Some(87.0));
+        assert!(ups.is_on_battery);
+        assert_eq!(ups.time_remaining_minutes, Some(14));
+    }
+
+    #[test]
+    fn test_sleep_timeline_from_pmset_log() {
+        let timeline = sleep::sleep_timeline(
+            &replay("macbook"),
+            &BatteryHistory::in_memory(),
+            0,
+            u64::MAX,
+        )
+        .unwrap();
+        assert_eq!(timeline.events.len(), 4);
+        assert_eq!(timeline.periods.len(), 1);
+
+        let period = &timeline.periods[0];
+        assert_eq!(period.sleep_reason, "Clamshell Sleep");
+        assert_eq!(period.wake_reason, "EC.LidOpen/Lid Open");
+        assert_eq!(period.duration_minutes, 507);
+        assert_eq!(period.dark_wake_count, 1);
+        assert_eq!(period.drain_percentage, Some(6.0));
+    }
+
+    #[test]
+    fn test_sleep_assertions_from_pmset() {
+        let assertions = sleep::sleep_assertions(&replay("macbook")).unwrap();
+        assert_eq!(assertions.len(), 2);
+        assert_eq!(assertions[0].pid, 2287);
+        assert_eq!(assertions[0].process_name, "Dropbox Helper");
+        assert_eq!(assertions[0].held_seconds, Some(50811));
+        assert_eq!(
+            assertions[1].name.as_deref(),
+            Some("com.apple.backupd.Backup")
+        );
+    }
+
+    #[test]
+    fn test_desktop_has_no_battery() {
+        assert!(replay("desktop").battery().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_monitors_on_recorded_macbook() {
+        let probe = replay("macbook");
+
+        let info = battery::battery_info(&probe).unwrap();
+        assert!((info.percentage - 88.93).abs() < 0.01);
+        assert_eq!(info.condition, "Normal");
+        // AvgTimeToEmpty rather than the instantaneous TimeRemaining
+        assert_eq!(info.time_to_empty_minutes, Some(405));
+        assert_eq!(info.time_to_full_minutes, None);
+        assert_eq!(info.power_draw_watts, Some(11.546));
+        assert_eq!(info.cell_imbalance_mv, Some(1));
+        assert_eq!(info.low_power_mode, Some(true));
+
+        let info = cpu::cpu_info(&probe).unwrap();
+        assert!((info.total_usage_percentage - 46.43).abs() < 0.01);
+        assert!(!info.is_estimate);
+        assert_eq!(info.breakdown.unwrap().system_percentage, 19.57);
+        let thermal = info.thermal.unwrap();
+        assert_eq!(thermal.pressure, cpu::ThermalPressure::Nominal);
+        assert_eq!(thermal.cpu_speed_limit_percentage, None);
+        assert_eq!(info.physical_cores, Some(10));
+        assert_eq!(info.performance_cores, Some(8));
+        assert_eq!(info.efficiency_cores, Some(2));
+        assert_eq!(info.clusters[0].name, "Performance");
+        assert_eq!(info.clusters[0].cache.l2_bytes, Some(16777216));
+
+        let processes = ram::top_memory_processes(&probe, Some(2)).unwrap();
+        assert_eq!(processes[0].pid, 3021);
+        assert_eq!(processes[1].name, "WindowServer");
+    }
+
+    #[test]
+    fn test_charging_times_on_recorded_macbooks() {
+        // TimeRemaining is still estimating here, AvgTimeToFull isn't
+        let info = battery::battery_info(&replay("fast_charging")).unwrap();
+        assert!(info.is_charging);
+        assert_eq!(info.time_to_full_minutes, Some(47));
+        assert_eq!(info.time_to_empty_minutes, None);
+
+        let info = battery::battery_info(&replay("macbook")).unwrap();
+        assert!(!info.is_charging);
+        assert_eq!(info.time_to_full_minutes, None);
+        assert_eq!(info.time_to_empty_minutes, Some(405));
+    }
+
+    #[test]
+    fn test_small_adapter_on_recorded_macbook() {
+        let info = battery::battery_info(&replay("charging")).unwrap();
+        assert_eq!(info.power_source, "AC Adapter");
+        assert_eq!(info.power_draw_watts, Some(39.466));
+        assert_eq!(info.low_power_mode, None);
+
+        let adapter = info.adapter.unwrap();
+        assert_eq!(adapter.watts, Some(30));
+        assert_eq!(adapter.description.as_deref(), Some("pd charger"));
+        assert!(adapter.is_underpowered);
+    }
+
+    #[test]
+    fn test_monitors_on_recorded_desktop() {
+        let probe = replay("desktop");
+
+        let err = battery::battery_info(&probe).unwrap_err();
+        assert_eq!(err, MonitorError::NoBattery);
+
+        let info = cpu::cpu_info(&probe).unwrap();
+        assert!((info.total_usage_percentage - 3.1).abs() < 0.01);
+        let thermal = info.thermal.unwrap();
+        assert_eq!(thermal.pressure, cpu::ThermalPressure::Moderate);
+        assert!(thermal.is_throttled);
+        assert_eq!(thermal.available_cpus, Some(8));
+    }
+}
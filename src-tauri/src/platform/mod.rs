@@ -0,0 +1,605 @@
+//! Platform backends the monitors read raw system readings from.
+//!
+//! Each monitor turns the readings returned by a [`SystemProbe`] into the
+//! payloads the frontend consumes, so the derivation logic is shared between
+//! platforms and can be exercised with a fake probe in tests.
+
+mod ioreg;
+mod linux;
+mod macos;
+mod pmset;
+mod runner;
+mod sysctl;
+mod system_cache;
+
+use std::path::Path;
+use std::sync::Arc;
+use sysinfo::System;
+
+use crate::error::MonitorError;
+
+pub use linux::LinuxProbe;
+pub use macos::MacProbe;
+#[cfg(test)]
+pub use runner::ReplayRunner;
+pub use runner::{CommandOutput, CommandRunner, SharedRunner, SystemRunner};
+pub use system_cache::SystemCache;
+
+/// Probe shared between Tauri commands through managed state.
+pub type SharedProbe = Arc<dyn SystemProbe>;
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryReading {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessMemoryReading {
+    pub pid: u32,
+    pub command: String,
+    pub rss_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessCpuReading {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_percentage: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CpuIdentity {
+    pub model_name: String,
+    pub core_count: usize,
+}
+
+/// How the cores are laid out, and the caches they share.
+#[derive(Debug, Clone, Default)]
+pub struct CpuTopologyReading {
+    pub physical_cores: Option<usize>,
+    pub logical_cores: Option<usize>,
+    /// Performance levels, fastest first; empty when all cores are alike
+    pub perf_levels: Vec<PerfLevelReading>,
+    pub cache: CacheSizes,
+}
+
+/// One performance level, e.g. the efficiency cores of an Apple Silicon CPU.
+#[derive(Debug, Clone, Default)]
+pub struct PerfLevelReading {
+    pub name: String,
+    pub physical_cores: usize,
+    pub logical_cores: usize,
+    /// How many cores share each L2 cache
+    pub cores_per_l2: Option<usize>,
+    pub cache: CacheSizes,
+}
+
+/// Cache sizes per core, except for the shared L2 and L3.
+#[derive(Debug, Clone, Default)]
+pub struct CacheSizes {
+    pub l1_instruction_bytes: Option<u64>,
+    pub l1_data_bytes: Option<u64>,
+    pub l2_bytes: Option<u64>,
+    pub l3_bytes: Option<u64>,
+}
+
+/// CPU clock speeds in MHz.
+#[derive(Debug, Clone, Default)]
+pub struct CpuFrequencyReading {
+    /// Current clock of each logical core; empty when it can't be read
+    pub per_core_mhz: Vec<Option<u64>>,
+    /// The rated base clock
+    pub nominal_mhz: Option<u64>,
+    pub max_mhz: Option<u64>,
+}
+
+/// CPU utilisation in percent, taken from one sample.
+#[derive(Debug, Clone, Default)]
+pub struct CpuUsageReading {
+    pub total_percentage: f32,
+    /// Per-core usage, empty when the platform cannot measure it.
+    pub per_core: Vec<f32>,
+    /// Where the time went, when the platform breaks it down.
+    pub breakdown: Option<CpuBreakdownReading>,
+}
+
+/// Share of CPU time per state, in percent of the whole machine.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuBreakdownReading {
+    pub user: f32,
+    pub system: f32,
+    pub idle: f32,
+    /// User time at lowered priority; macOS counts it as user time
+    pub nice: Option<f32>,
+    /// Idle while waiting on disk I/O, Linux only
+    pub iowait: Option<f32>,
+    /// Taken by the hypervisor for other guests, Linux only
+    pub steal: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadReading {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+/// Raw battery gauge values, in the units the hardware reports them.
+#[derive(Debug, Clone, Default)]
+pub struct BatteryReading {
+    pub current_capacity: f32,
+    pub max_capacity: f32,
+    pub design_capacity: f32,
+    pub is_charging: bool,
+    pub external_connected: bool,
+    pub cycle_count: Option<u32>,
+    pub temperature_celsius: Option<f32>,
+    pub voltage_volts: Option<f32>,
+    /// Whichever way the battery is going, when only one estimate is given
+    pub time_remaining_minutes: Option<u32>,
+    /// The gauge's own estimates, when it keeps them apart
+    pub time_to_full_minutes: Option<u32>,
+    pub time_to_empty_minutes: Option<u32>,
+    /// Current through the battery, negative while discharging
+    pub amperage_ma: Option<f32>,
+    /// Power the whole system is drawing, when the platform measures it
+    pub system_power_watts: Option<f32>,
+    /// Per-cell voltages, empty when the gauge doesn't report them
+    pub cell_voltages_mv: Vec<u32>,
+    /// The connected power adapter, when the platform describes it
+    pub adapter: Option<AdapterReading>,
+    pub identity: BatteryIdentity,
+}
+
+/// What the battery reports about itself.
+#[derive(Debug, Clone, Default)]
+pub struct BatteryIdentity {
+    pub serial: Option<String>,
+    pub manufacturer: Option<String>,
+    pub device_name: Option<String>,
+    pub manufacture_date: Option<ManufactureDate>,
+    /// `None` when the gauge counts energy rather than charge
+    pub design_capacity_mah: Option<u32>,
+    pub full_charge_capacity_mah: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ManufactureDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl ManufactureDate {
+    /// `None` unless the date exists on the calendar.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        (1..=days_in_month)
+            .contains(&day)
+            .then_some(ManufactureDate { year, month, day })
+    }
+
+    /// Days since 1970-01-01.
+    pub fn days_since_epoch(&self) -> i64 {
+        days_from_civil(self.year as i64, self.month, self.day)
+    }
+}
+
+/// Days since 1970-01-01 of a date on the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    // Howard Hinnant's days_from_civil
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Power adapter as negotiated with the machine.
+#[derive(Debug, Clone, Default)]
+pub struct AdapterReading {
+    pub watts: Option<u32>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// IOKit power source family code
+    pub family_code: Option<i64>,
+    pub voltage_mv: Option<u32>,
+    pub current_ma: Option<u32>,
+    pub is_wireless: bool,
+}
+
+/// The power sources macOS knows about and which one powers the machine.
+#[derive(Debug, Clone, Default)]
+pub struct PowerSourcesReading {
+    pub drawing_from: DrawingFrom,
+    pub sources: Vec<PowerSourceReading>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DrawingFrom {
+    #[default]
+    Ac,
+    Battery,
+    Ups,
+}
+
+/// A battery-backed power source: the internal battery or a UPS.
+#[derive(Debug, Clone, Default)]
+pub struct PowerSourceReading {
+    pub name: String,
+    pub is_internal: bool,
+    pub percentage: Option<f32>,
+    pub is_charging: bool,
+    /// `None` while the source is still estimating
+    pub time_remaining_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepEventKind {
+    Sleep,
+    Wake,
+    /// Woken for background work with the display off
+    DarkWake,
+}
+
+/// A sleep or wake entry from the power management log.
+#[derive(Debug, Clone)]
+pub struct SleepEventReading {
+    pub timestamp_ms: u64,
+    pub kind: SleepEventKind,
+    pub reason: String,
+    /// Battery charge the log recorded with the event
+    pub charge_percentage: Option<f32>,
+    /// `None` when the log doesn't say what the machine was running on
+    pub on_battery: Option<bool>,
+}
+
+/// A power assertion a process holds, from `pmset -g assertions`.
+#[derive(Debug, Clone)]
+pub struct AssertionReading {
+    pub pid: u32,
+    pub process_name: String,
+    /// e.g. `PreventUserIdleSystemSleep`
+    pub assertion_type: String,
+    /// What the process called the assertion
+    pub name: Option<String>,
+    pub held_seconds: Option<u64>,
+}
+
+/// Thermal throttling state from `pmset -g therm`. Each field is `None`
+/// when nothing has been recorded for it since boot.
+#[derive(Debug, Clone, Default)]
+pub struct ThermalReading {
+    /// Percentage of its maximum speed the CPU is allowed to run at
+    pub cpu_speed_limit: Option<u32>,
+    /// Percentage of time the scheduler may run work on the CPUs
+    pub scheduler_limit: Option<u32>,
+    pub available_cpus: Option<u32>,
+    /// 0 is normal, 5 danger and 10 crisis
+    pub thermal_warning_level: Option<u32>,
+    pub performance_warning_level: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiskReading {
+    pub name: String,
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub file_system: String,
+    pub is_removable: bool,
+}
+
+/// Source of raw system readings for the monitors.
+///
+/// The defaults use `sysinfo`, which behaves the same on every platform;
+/// implementations override whatever their platform exposes more accurately.
+pub trait SystemProbe: Send + Sync {
+    /// The probe's long-lived `sysinfo` state, used by the defaults.
+    fn system_cache(&self) -> &SystemCache;
+
+    fn memory(&self) -> Result<MemoryReading, MonitorError> {
+        Ok(self.system_cache().memory())
+    }
+
+    fn memory_processes(&self) -> Result<Vec<ProcessMemoryReading>, MonitorError> {
+        Ok(self.system_cache().memory_processes())
+    }
+
+    fn cpu_processes(&self) -> Result<Vec<ProcessCpuReading>, MonitorError> {
+        Ok(self.system_cache().cpu_processes())
+    }
+
+    fn cpu_identity(&self) -> CpuIdentity {
+        self.system_cache().cpu_identity()
+    }
+
+    fn cpu_topology(&self) -> CpuTopologyReading {
+        self.system_cache().cpu_topology()
+    }
+
+    fn cpu_frequency(&self) -> CpuFrequencyReading {
+        CpuFrequencyReading {
+            per_core_mhz: self.system_cache().per_core_frequency(),
+            ..Default::default()
+        }
+    }
+
+    /// CPU usage, or `None` when it cannot be measured.
+    fn cpu_usage(&self) -> Option<CpuUsageReading>;
+
+    fn load_average(&self) -> LoadReading {
+        let load = System::load_average();
+        LoadReading {
+            one: load.one,
+            five: load.five,
+            fifteen: load.fifteen,
+        }
+    }
+
+    fn uptime(&self) -> u64 {
+        System::uptime()
+    }
+
+    /// Battery readings, or `Ok(None)` when the machine has no battery.
+    fn battery(&self) -> Result<Option<BatteryReading>, MonitorError>;
+
+    /// Whether Low Power Mode is on, `None` when the platform has none.
+    fn low_power_mode(&self) -> Option<bool> {
+        None
+    }
+
+    /// Power sources, or `Ok(None)` when the platform cannot enumerate them.
+    fn power_sources(&self) -> Result<Option<PowerSourcesReading>, MonitorError> {
+        Ok(None)
+    }
+
+    /// Sleep and wake events, oldest first.
+    fn sleep_log(&self) -> Result<Vec<SleepEventReading>, MonitorError> {
+        Err(MonitorError::Unsupported("Sleep history".to_string()))
+    }
+
+    /// Power assertions held by processes.
+    fn assertions(&self) -> Result<Vec<AssertionReading>, MonitorError> {
+        Err(MonitorError::Unsupported("Power assertions".to_string()))
+    }
+
+    /// Thermal throttling state, `None` when the platform doesn't report it.
+    fn thermal(&self) -> Option<ThermalReading> {
+        None
+    }
+
+    fn disks(&self) -> Vec<DiskReading> {
+        self.system_cache().disks()
+    }
+
+    /// Size of a directory tree in bytes, or 0 when it cannot be measured.
+    fn dir_size(&self, path: &Path) -> u64;
+}
+
+/// Runner that executes commands on the host.
+pub fn default_runner() -> SharedRunner {
+    Arc::new(SystemRunner)
+}
+
+/// Probe for the platform the app was built for.
+pub fn default_probe(runner: SharedRunner) -> SharedProbe {
+    if cfg!(target_os = "macos") {
+        Arc::new(MacProbe::new(runner))
+    } else {
+        Arc::new(LinuxProbe::new(runner))
+    }
+}
+
+/// Parse `du -sk` output into bytes.
+fn parse_du_output(stdout: &str) -> Option<u64> {
+    stdout
+        .split_whitespace()
+        .next()
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// Get directory size using du -s (fast, single line output)
+fn du_dir_size(runner: &dyn CommandRunner, path: &Path) -> u64 {
+    if !path.exists() {
+        return 0;
+    }
+
+    match runner.run("du", &["-sk", path.to_string_lossy().as_ref()]) {
+        Ok(out) if out.success() => parse_du_output(&out.stdout).unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Run a tool and return its stdout, failing on a non-zero exit.
+fn run_tool(
+    runner: &dyn CommandRunner,
+    program: &str,
+    args: &[&str],
+) -> Result<String, MonitorError> {
+    let output = runner
+        .run(program, args)
+        .map_err(|e| MonitorError::spawn_failed(program, e))?;
+
+    if !output.success() {
+        return Err(MonitorError::command_failed(program, &output));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Run a shell command as root behind the macOS administrator password
+/// prompt. `Ok(false)` means the user cancelled the prompt.
+pub fn run_with_admin(runner: &dyn CommandRunner, command: &str) -> Result<bool, MonitorError> {
+    let script = format!(
+        r#"do shell script "{}" with administrator privileges"#,
+        command
+    );
+
+    let output = runner
+        .run("osascript", &["-e", &script])
+        .map_err(|e| MonitorError::spawn_failed("osascript", e))?;
+
+    if !output.success() {
+        // User cancelled or authentication failed
+        if output.stderr.contains("canceled") || output.stderr.contains("User canceled") {
+            return Ok(false);
+        }
+        return Err(MonitorError::command_failed("osascript", &output));
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    /// Runner serving the recordings of one scenario under
+    /// `tests/fixtures/commands`.
+    pub fn replay(scenario: &str) -> ReplayRunner {
+        ReplayRunner::new(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/commands")
+                .join(scenario),
+        )
+    }
+
+    /// Probe returning canned readings, for testing monitor logic.
+    #[derive(Default)]
+    pub struct FakeProbe {
+        pub memory: MemoryReading,
+        pub memory_processes: Vec<ProcessMemoryReading>,
+        pub cpu_processes: Vec<ProcessCpuReading>,
+        pub cpu_identity: CpuIdentity,
+        pub cpu_topology: CpuTopologyReading,
+        pub cpu_frequency: CpuFrequencyReading,
+        pub cpu_usage: Option<CpuUsageReading>,
+        pub load: LoadReading,
+        pub battery: Option<BatteryReading>,
+        pub low_power_mode: Option<bool>,
+        pub power_sources: Option<PowerSourcesReading>,
+        pub sleep_log: Vec<SleepEventReading>,
+        pub assertions: Vec<AssertionReading>,
+        pub thermal: Option<ThermalReading>,
+        pub disks: Vec<DiskReading>,
+        pub system_cache: SystemCache,
+    }
+
+    impl SystemProbe for FakeProbe {
+        fn system_cache(&self) -> &SystemCache {
+            &self.system_cache
+        }
+
+        fn memory(&self) -> Result<MemoryReading, MonitorError> {
+            Ok(self.memory.clone())
+        }
+
+        fn memory_processes(&self) -> Result<Vec<ProcessMemoryReading>, MonitorError> {
+            Ok(self.memory_processes.clone())
+        }
+
+        fn cpu_processes(&self) -> Result<Vec<ProcessCpuReading>, MonitorError> {
+            Ok(self.cpu_processes.clone())
+        }
+
+        fn cpu_identity(&self) -> CpuIdentity {
+            self.cpu_identity.clone()
+        }
+
+        fn cpu_topology(&self) -> CpuTopologyReading {
+            self.cpu_topology.clone()
+        }
+
+        fn cpu_frequency(&self) -> CpuFrequencyReading {
+            self.cpu_frequency.clone()
+        }
+
+        fn cpu_usage(&self) -> Option<CpuUsageReading> {
+            self.cpu_usage.clone()
+        }
+
+        fn load_average(&self) -> LoadReading {
+            self.load
+        }
+
+        fn uptime(&self) -> u64 {
+            3600
+        }
+
+        fn battery(&self) -> Result<Option<BatteryReading>, MonitorError> {
+            Ok(self.battery.clone())
+        }
+
+        fn low_power_mode(&self) -> Option<bool> {
+            self.low_power_mode
+        }
+
+        fn power_sources(&self) -> Result<Option<PowerSourcesReading>, MonitorError> {
+            Ok(self.power_sources.clone())
+        }
+
+        fn sleep_log(&self) -> Result<Vec<SleepEventReading>, MonitorError> {
+            Ok(self.sleep_log.clone())
+        }
+
+        fn assertions(&self) -> Result<Vec<AssertionReading>, MonitorError> {
+            Ok(self.assertions.clone())
+        }
+
+        fn thermal(&self) -> Option<ThermalReading> {
+            self.thermal.clone()
+        }
+
+        fn disks(&self) -> Vec<DiskReading> {
+            self.disks.clone()
+        }
+
+        fn dir_size(&self, _path: &Path) -> u64 {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_du_output() {
+        assert_eq!(
+            parse_du_output("2048\t/Users/me/Documents\n"),
+            Some(2048 * 1024)
+        );
+        assert_eq!(parse_du_output(""), None);
+    }
+
+    #[test]
+    fn test_manufacture_date() {
+        let date = |y, m, d| ManufactureDate::new(y, m, d);
+        assert_eq!(date(1970, 1, 1).unwrap().days_since_epoch(), 0);
+        assert_eq!(date(2023, 1, 21).unwrap().days_since_epoch(), 19378);
+        assert_eq!(date(2024, 3, 1).unwrap().days_since_epoch(), 19783);
+        assert!(date(2024, 2, 29).is_some());
+        assert!(date(2023, 2, 29).is_none());
+        assert!(date(2023, 13, 1).is_none());
+    }
+
+    #[test]
+    fn test_default_probe_reads_memory() {
+        let probe = default_probe(default_runner());
+        let memory = probe.memory().unwrap();
+        assert!(memory.total_bytes > 0);
+    }
+}
@@ -0,0 +1,451 @@
+//! Parsers for `pmset` output.
+//!
+//! The power source listing printed by `pmset -g ps` (and its alias
+//! `pmset -g batt`) covers UPS units connected over USB, which `ioreg` does
+//! not, and they are the only power source a desktop Mac reports:
+//!
+//! ```text
+//! Now drawing from 'UPS Power'
+//!  -Back-UPS ES 700G FW:871.O2 .I USB FW:O2 (id=8519680)    87%; discharging; 0:14 remaining present: true
+//! ```
+//!
+//! The power management log printed by `pmset -g log` records every sleep
+//! and wake with its reason and the battery charge at the time:
+//!
+//! ```text
+//! 2024-01-20 23:14:02 -0800 Sleep                   Entering Sleep state due to 'Idle Sleep':TCPKeepAlive=active Using Batt (Charge:85%)    3600 secs
+//! ```
+//!
+//! `pmset -g` lists the settings in effect for the current power source,
+//! including `lowpowermode` (`powermode` on machines that also have a High
+//! Power Mode).
+//!
+//! `pmset -g assertions` lists the assertions each process holds to keep
+//! the machine or its display awake:
+//!
+//! ```text
+//! Listed by owning process:
+//!    pid 412(backupd): [0x0000000a000192c2] 01:02:11 PreventUserIdleSystemSleep named: "com.apple.backupd.Backup"
+//! ```
+//!
+//! `pmset -g therm` reports the thermal and performance warning levels and,
+//! on Intel Macs, the limits placed on the CPU when it runs hot:
+//!
+//! ```text
+//! Note: No thermal warning level has been recorded
+//! Note: No performance warning level has been recorded
+//! 2024-01-21 10:02:11 -0800 CPU Power notify
+//!     CPU_Scheduler_Limit     = 100
+//!     CPU_Available_CPUs      = 8
+//!     CPU_Speed_Limit         = 78
+//! ```
+
+use crate::error::MonitorError;
+
+use super::{
+    days_from_civil, AssertionReading, DrawingFrom, PowerSourceReading, PowerSourcesReading,
+    SleepEventKind, SleepEventReading, ThermalReading,
+};
+
+pub fn parse_power_sources(stdout: &str) -> Result<PowerSourcesReading, MonitorError> {
+    let mut lines = stdout.lines();
+    let drawing_from = lines
+        .next()
+        .and_then(|line| line.trim().strip_prefix("Now drawing from '"))
+        .and_then(|rest| rest.strip_suffix('\''))
+        .map(|name| match name {
+            "Battery Power" => DrawingFrom::Battery,
+            "UPS Power" => DrawingFrom::Ups,
+            _ => DrawingFrom::Ac,
+        })
+        .ok_or_else(|| MonitorError::ParseError("pmset power sources".to_string()))?;
+
+    let sources = lines.filter_map(parse_source).collect();
+    Ok(PowerSourcesReading {
+        drawing_from,
+        sources,
+    })
+}
+
+/// One ` -<name> (id=<n>)\t<percent>%; <state>; <time> present: true` line.
+fn parse_source(line: &str) -> Option<PowerSourceReading> {
+    let line = line.trim().strip_prefix('-')?;
+    let id_start = line.find(" (id=")?;
+    let name = line[..id_start].trim().to_string();
+    let rest = &line[id_start..];
+    let status = &rest[rest.find(')')? + 1..];
+
+    let mut fields = status.split(';').map(str::trim);
+    let percentage = fields
+        .next()
+        .and_then(|field| field.strip_suffix('%'))
+        .and_then(|percent| percent.parse::<f32>().ok());
+    let state = fields.next().unwrap_or("");
+    let is_charging = matches!(state, "charging" | "finishing charge");
+
+    // The time is to full while charging and to empty while discharging;
+    // in any other state it is a meaningless 0:00
+    let time_remaining_minutes = match state {
+        "charging" | "finishing charge" | "discharging" => fields.next().and_then(parse_remaining),
+        _ => None,
+    };
+
+    Some(PowerSourceReading {
+        is_internal: name.starts_with("InternalBattery"),
+        name,
+        percentage,
+        is_charging,
+        time_remaining_minutes,
+    })
+}
+
+/// `1:23 remaining present: true`, or `(no estimate) present: true`.
+fn parse_remaining(field: &str) -> Option<u32> {
+    let (hours, minutes) = field.split_whitespace().next()?.split_once(':')?;
+    Some(hours.parse::<u32>().ok()? * 60 + minutes.parse::<u32>().ok()?)
+}
+
+/// Whether Low Power Mode is on in `pmset -g` output, `None` on releases
+/// without it.
+pub fn parse_low_power_mode(stdout: &str) -> Option<bool> {
+    stdout.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        match (fields.next()?, fields.next()?) {
+            ("lowpowermode" | "powermode", value) => Some(value == "1"),
+            _ => None,
+        }
+    })
+}
+
+/// Sleep, wake and dark wake entries of `pmset -g log`. Every other kind of
+/// entry, and lines that don't parse, are skipped.
+pub fn parse_sleep_log(stdout: &str) -> Vec<SleepEventReading> {
+    stdout.lines().filter_map(parse_log_entry).collect()
+}
+
+/// `<date> <time> <offset> <kind>\t<description>\t<duration>`
+fn parse_log_entry(line: &str) -> Option<SleepEventReading> {
+    let mut columns = line.split('\t');
+    let mut head = columns.next()?.split_whitespace();
+    let timestamp_ms = parse_log_timestamp(head.next()?, head.next()?, head.next()?)?;
+    let kind = match head.collect::<Vec<_>>().join(" ").as_str() {
+        "Sleep" => SleepEventKind::Sleep,
+        "Wake" => SleepEventKind::Wake,
+        "DarkWake" => SleepEventKind::DarkWake,
+        _ => return None,
+    };
+    let description = columns.next()?.trim();
+
+    let on_battery = if description.contains("Using AC") {
+        Some(false)
+    } else if description.contains("Using Batt") || description.contains("Using BATT") {
+        Some(true)
+    } else {
+        None
+    };
+    let charge_percentage = description
+        .split_once("(Charge:")
+        .and_then(|(_, rest)| rest.split_once('%'))
+        .and_then(|(charge, _)| charge.trim().parse::<f32>().ok());
+
+    Some(SleepEventReading {
+        timestamp_ms,
+        kind,
+        reason: parse_log_reason(description),
+        charge_percentage,
+        on_battery,
+    })
+}
+
+/// The text after `due to`: quoted for sleeps (`'Idle Sleep':...`), running
+/// up to the power state for wakes (`EC.LidOpen/Lid Open Using BATT ...`).
+fn parse_log_reason(description: &str) -> String {
+    let reason = match description.split_once("due to ") {
+        Some((_, reason)) => reason,
+        None => return description.to_string(),
+    };
+    let reason = match reason.strip_prefix('\'') {
+        Some(quoted) => quoted.split('\'').next().unwrap_or(quoted),
+        None => reason.split(" Using ").next().unwrap_or(reason),
+    };
+    reason.trim().to_string()
+}
+
+/// `2024-01-20`, `23:14:02`, `-0800` in milliseconds since the epoch.
+fn parse_log_timestamp(date: &str, time: &str, offset: &str) -> Option<u64> {
+    let mut date = date.split('-').map(str::parse::<u16>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time.split(':').map(str::parse::<i64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    let sign = match offset.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    // Exactly four digits, so slicing can't land inside a character
+    let offset = offset.get(1..5)?;
+    if !offset.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = (offset.get(..2)?, offset.get(2..)?);
+    let offset_seconds =
+        sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60);
+
+    let days = days_from_civil(year as i64, month as u8, day as u8);
+    let seconds = days * 86_400 + hour * 3600 + minute * 60 + second - offset_seconds;
+    u64::try_from(seconds * 1000).ok()
+}
+
+/// The per-process entries of `pmset -g assertions`. Their indented detail
+/// lines, and the system-wide and kernel sections, are skipped.
+pub fn parse_assertions(stdout: &str) -> Vec<AssertionReading> {
+    stdout.lines().filter_map(parse_assertion).collect()
+}
+
+/// `pid <pid>(<process>): [<id>] <HH:MM:SS> <type> named: "<name>"`
+fn parse_assertion(line: &str) -> Option<AssertionReading> {
+    let line = line.trim().strip_prefix("pid ")?;
+    let (owner, rest) = line.split_once(": [")?;
+    let (pid, process_name) = owner.split_once('(')?;
+    let process_name = process_name.strip_suffix(')')?;
+    let (_, rest) = rest.split_once(']')?;
+
+    let mut tokens = rest.split_whitespace().peekable();
+    let held_seconds = tokens.peek().and_then(|token| parse_held(token));
+    if held_seconds.is_some() {
+        tokens.next();
+    }
+    let assertion_type = tokens.next()?.to_string();
+    let name = rest
+        .split_once("named: \"")
+        .and_then(|(_, name)| name.rsplit_once('"'))
+        .map(|(name, _)| name.to_string());
+
+    Some(AssertionReading {
+        pid: pid.trim().parse().ok()?,
+        process_name: process_name.to_string(),
+        assertion_type,
+        name,
+        held_seconds,
+    })
+}
+
+/// `HH:MM:SS`, where the hours keep counting past a day.
+fn parse_held(token: &str) -> Option<u64> {
+    let mut parts = token.split(':').map(str::parse::<u64>);
+    let (hours, minutes, seconds) = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// The warning levels and CPU limits in `pmset -g therm` output. Levels
+/// that have never been recorded, and the CPU limits Apple Silicon doesn't
+/// report, are left as `None`.
+pub fn parse_thermal(stdout: &str) -> ThermalReading {
+    let mut reading = ThermalReading::default();
+    for line in stdout.lines() {
+        let line = line.trim();
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().parse().ok();
+            match key.trim() {
+                "CPU_Speed_Limit" => reading.cpu_speed_limit = value,
+                "CPU_Scheduler_Limit" => reading.scheduler_limit = value,
+                "CPU_Available_CPUs" => reading.available_cpus = value,
+                _ => {}
+            }
+            continue;
+        }
+        // e.g. "Thermal warning level has been set to 5"
+        let lower = line.to_ascii_lowercase();
+        let level = || line.split_whitespace().last()?.parse().ok();
+        if lower.starts_with("thermal warning level") {
+            reading.thermal_warning_level = level();
+        } else if lower.starts_with("performance warning level") {
+            reading.performance_warning_level = level();
+        }
+    }
+    reading
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_battery() {
+        let reading = parse_power_sources(
+            "Now drawing from 'Battery Power'\n \
+             -InternalBattery-0 (id=4653155)\t85%; discharging; 6:52 remaining present: true\n",
+        )
+        .unwrap();
+        assert_eq!(reading.drawing_from, DrawingFrom::Battery);
+        assert_eq!(reading.sources.len(), 1);
+
+        let battery = &reading.sources[0];
+        assert_eq!(battery.name, "InternalBattery-0");
+        assert!(battery.is_internal);
+        assert_eq!(battery.percentage, Some(85.0));
+        assert!(!battery.is_charging);
+        assert_eq!(battery.time_remaining_minutes, Some(412));
+    }
+
+    #[test]
+    fn test_ups_states() {
+        let reading = parse_power_sources(
+            "Now drawing from 'AC Power'\n \
+             -CP1500PFCLCDa (id=4325376)\t100%; AC attached; not charging present: true\n",
+        )
+        .unwrap();
+        assert_eq!(reading.drawing_from, DrawingFrom::Ac);
+        let ups = &reading.sources[0];
+        assert_eq!(ups.name, "CP1500PFCLCDa");
+        assert!(!ups.is_internal);
+        assert!(!ups.is_charging);
+        assert_eq!(ups.time_remaining_minutes, None);
+
+        let reading = parse_power_sources(
+            "Now drawing from 'AC Power'\n \
+             -CP1500PFCLCDa (id=4325376)\t64%; charging; (no estimate) present: true\n",
+        )
+        .unwrap();
+        assert!(reading.sources[0].is_charging);
+        assert_eq!(reading.sources[0].time_remaining_minutes, None);
+    }
+
+    #[test]
+    fn test_no_sources() {
+        let reading = parse_power_sources("Now drawing from 'AC Power'\n").unwrap();
+        assert!(reading.sources.is_empty());
+
+        let err = parse_power_sources("").unwrap_err();
+        assert_eq!(err.code(), "ParseError");
+    }
+
+    #[test]
+    fn test_low_power_mode() {
+        let settings = "System-wide power settings:\nCurrently in use:\n standby              1\n";
+        assert_eq!(parse_low_power_mode(settings), None);
+        assert_eq!(
+            parse_low_power_mode(&format!("{} lowpowermode         1\n", settings)),
+            Some(true)
+        );
+        // 2 is High Power Mode
+        assert_eq!(
+            parse_low_power_mode(&format!("{} powermode            2\n", settings)),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_sleep_log() {
+        let events = parse_sleep_log(concat!(
+            "2024-01-20 23:14:02 -0800 Sleep               \tEntering Sleep state due to 'Idle Sleep':TCPKeepAlive=active Using Batt (Charge:85%) \t3600 secs\n",
+            "2024-01-21 02:15:33 -0800 Assertions          \tPID 412(backupd) Released PreventUserIdleSystemSleep\n",
+            "2024-01-21 02:15:33 -0800 DarkWake            \tDarkWake from Deep Idle [CDNP] : due to RTC/Maintenance Using BATT (Charge:83%) \t45 secs\n",
+            "2024-01-21 07:30:01 -0800 Wake                \tWake from Deep Idle [CDNVA] : due to EC.LidOpen/Lid Open Using AC (Charge:80%)\t\n",
+            "2024-01-21 07:30:01 -0800 Kernel Client Acks  \tDelays to Wake notifications: [AppleSmartBattery driver is slow]\n",
+        ));
+        assert_eq!(events.len(), 3);
+
+        assert_eq!(events[0].kind, SleepEventKind::Sleep);
+        // 2024-01-21T07:14:02Z
+        assert_eq!(events[0].timestamp_ms, 1_705_821_242_000);
+        assert_eq!(events[0].reason, "Idle Sleep");
+        assert_eq!(events[0].charge_percentage, Some(85.0));
+        assert_eq!(events[0].on_battery, Some(true));
+
+        assert_eq!(events[1].kind, SleepEventKind::DarkWake);
+        assert_eq!(events[1].reason, "RTC/Maintenance");
+
+        assert_eq!(events[2].kind, SleepEventKind::Wake);
+        assert_eq!(events[2].reason, "EC.LidOpen/Lid Open");
+        assert_eq!(events[2].charge_percentage, Some(80.0));
+        assert_eq!(events[2].on_battery, Some(false));
+    }
+
+    #[test]
+    fn test_assertions() {
+        let assertions = parse_assertions(concat!(
+            "Assertion status system-wide:\n",
+            "   PreventUserIdleSystemSleep     1\n",
+            "Listed by owning process:\n",
+            "   pid 412(backupd): [0x0000000a000192c2] 01:02:11 PreventUserIdleSystemSleep named: \"com.apple.backupd.Backup\"  \n",
+            "\tTimeout will fire in 3600 secs Action=TimeoutActionRelease\n",
+            "   pid 1021(Microsoft Teams (work)): [0x000000040001a2b3] 27:00:05 PreventUserIdleDisplaySleep named: \"Call \"standup\"\"\n",
+            "   pid 88(coreaudiod): [0x0000000900019d4c] NoIdleSleepAssertion named: \"audio\"\n",
+            "Kernel Assertions: 0x4=USB\n",
+            "   id=500  level=255 0x4=USB mod=1/1/70, 10:00 AM description=com.apple.usb.externaldevice owner=Keyboard\n",
+        ));
+        assert_eq!(assertions.len(), 3);
+
+        assert_eq!(assertions[0].pid, 412);
+        assert_eq!(assertions[0].process_name, "backupd");
+        assert_eq!(assertions[0].assertion_type, "PreventUserIdleSystemSleep");
+        assert_eq!(
+            assertions[0].name.as_deref(),
+            Some("com.apple.backupd.Backup")
+        );
+        assert_eq!(assertions[0].held_seconds, Some(3731));
+
+        assert_eq!(assertions[1].process_name, "Microsoft Teams (work)");
+        assert_eq!(assertions[1].name.as_deref(), Some("Call \"standup\""));
+        assert_eq!(assertions[1].held_seconds, Some(97205));
+
+        // Older releases don't print how long it has been held
+        assert_eq!(assertions[2].assertion_type, "NoIdleSleepAssertion");
+        assert_eq!(assertions[2].held_seconds, None);
+    }
+
+    #[test]
+    fn test_thermal() {
+        let notes = "Note: No thermal warning level has been recorded\n\
+                     Note: No performance warning level has been recorded\n";
+        let reading = parse_thermal(notes);
+        assert_eq!(reading.thermal_warning_level, None);
+        assert_eq!(reading.performance_warning_level, None);
+        assert_eq!(reading.cpu_speed_limit, None);
+
+        let reading = parse_thermal(concat!(
+            "Thermal warning level has been set to 5\n",
+            "Performance warning level has been set to 0\n",
+            "2024-01-21 10:02:11 -0800 CPU Power notify\n",
+            "\tCPU_Scheduler_Limit \t= 100\n",
+            "\tCPU_Available_CPUs \t= 8\n",
+            "\tCPU_Speed_Limit \t= 62\n",
+        ));
+        assert_eq!(reading.thermal_warning_level, Some(5));
+        assert_eq!(reading.performance_warning_level, Some(0));
+        assert_eq!(reading.scheduler_limit, Some(100));
+        assert_eq!(reading.available_cpus, Some(8));
+        assert_eq!(reading.cpu_speed_limit, Some(62));
+    }
+
+    #[test]
+    fn test_log_timestamp_offsets() {
+        assert_eq!(
+            parse_log_timestamp("1970-01-01", "01:00:00", "+0100"),
+            Some(0)
+        );
+        assert_eq!(
+            parse_log_timestamp("1970-01-01", "00:00:00", "-0530"),
+            Some(19_800_000)
+        );
+        assert_eq!(parse_log_timestamp("1970-01-01", "00:00:00", "+0100"), None);
+        assert_eq!(parse_log_timestamp("yesterday", "00:00:00", "+0000"), None);
+    }
+
+    #[test]
+    fn test_malformed_timezone_is_rejected() {
+        for offset in ["+0é0", "+é00", "+01", "0100", "+01:0", "++100", ""] {
+            assert_eq!(
+                parse_log_timestamp("1970-01-01", "00:00:00", offset),
+                None,
+                "{offset:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::io;
+#[cfg(test)]
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+/// Runner shared between the probe and commands through managed state.
+pub type SharedRunner = Arc<dyn CommandRunner>;
+
+/// Captured result of running an external tool.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    /// Exit code, `None` when the process was terminated by a signal
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// Executes external tools such as `ps`, `top` and `ioreg`.
+pub trait CommandRunner: Send + Sync {
+    /// Run `program` to completion. Errors mean it could not be started.
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+}
+
+/// Runs commands on the host.
+#[derive(Debug, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            status: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
+/// Serves recorded command output from a fixture directory.
+///
+/// A command is looked up by its key (see [`fixture_key`]) and then by the
+/// bare program name, so `ps -axm -o pid,rss,command` is served from
+/// `ps_axm_o_pid_rss_command.stdout` or, failing that, `ps.stdout`. Each
+/// recording is a set of files sharing that stem:
+///
+/// - `<stem>.stdout` and `<stem>.stderr`: captured output, empty if missing
+/// - `<stem>.status`: exit code, `0` if missing
+///
+/// A command with no recording at all fails with `NotFound`, the same way a
+/// missing tool does.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct ReplayRunner {
+    dir: PathBuf,
+}
+
+#[cfg(test)]
+impl ReplayRunner {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn recording(&self, stem: &str) -> Option<CommandOutput> {
+        let read =
+            |ext: &str| std::fs::read_to_string(self.dir.join(format!("{}.{}", stem, ext))).ok();
+
+        let stdout = read("stdout");
+        let status = read("status");
+        if stdout.is_none() && status.is_none() {
+            return None;
+        }
+
+        Some(CommandOutput {
+            status: match status {
+                Some(code) => code.trim().parse::<i32>().ok(),
+                None => Some(0),
+            },
+            stdout: stdout.unwrap_or_default(),
+            stderr: read("stderr").unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+impl CommandRunner for ReplayRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        self.recording(&fixture_key(program, args))
+            .or_else(|| self.recording(&fixture_key(program, &[])))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no recording for {} in {}", program, self.dir.display()),
+                )
+            })
+    }
+}
+
+/// File stem for a command: every run of non-alphanumerics becomes `_`.
+#[cfg(test)]
+pub fn fixture_key(program: &str, args: &[&str]) -> String {
+    let name = Path::new(program)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| program.to_string());
+
+    let mut key = String::new();
+    for part in std::iter::once(name.as_str()).chain(args.iter().copied()) {
+        for c in part.chars() {
+            if c.is_ascii_alphanumeric() {
+                key.push(c);
+            } else if !key.ends_with('_') {
+                key.push('_');
+            }
+        }
+        if !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    key.trim_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::testing::replay;
+
+    #[test]
+    fn test_fixture_key() {
+        assert_eq!(
+            fixture_key("ps", &["-axm", "-o", "pid,rss,command"]),
+            "ps_axm_o_pid_rss_command"
+        );
+        assert_eq!(fixture_key("/usr/bin/top", &["-l", "1"]), "top_l_1");
+        assert_eq!(fixture_key("ps", &[]), "ps");
+    }
+
+    #[test]
+    fn test_replay_runner_serves_recording() {
+        let output = replay("macbook")
+            .run("ioreg", &["-a", "-rc", "AppleSmartBattery"])
+            .unwrap();
+        assert!(output.success());
+        assert!(output.stdout.contains("AppleRawCurrentCapacity"));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_replay_runner_serves_failures() {
+        let output = replay("macbook")
+            .run("osascript", &["-e", "do shell script \"purge\""])
+            .unwrap();
+        assert_eq!(output.status, Some(1));
+        assert!(output.stderr.contains("User canceled"));
+    }
+
+    #[test]
+    fn test_replay_runner_missing_tool() {
+        let err = replay("macbook").run("diskutil", &["list"]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_system_runner_captures_exit_code() {
+        let output = SystemRunner
+            .run("sh", &["-c", "echo out; echo err >&2; exit 3"])
+            .unwrap();
+        assert_eq!(output.status, Some(3));
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+    }
+}
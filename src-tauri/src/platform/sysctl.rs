@@ -0,0 +1,160 @@
+//! Parsers for `sysctl` output.
+//!
+//! `sysctl hw` prints the hardware description one `name: value` pair per
+//! line. On Apple Silicon the cores are grouped into performance levels,
+//! fastest first, each with its own core counts and caches:
+//!
+//! ```text
+//! hw.physicalcpu: 10
+//! hw.logicalcpu: 10
+//! hw.nperflevels: 2
+//! hw.perflevel0.physicalcpu: 8
+//! hw.perflevel0.logicalcpu: 8
+//! hw.perflevel0.l2cachesize: 16777216
+//! hw.perflevel0.cpusperl2: 4
+//! hw.perflevel0.name: Performance
+//! hw.perflevel1.physicalcpu: 2
+//! ```
+//!
+//! Intel Macs report their clock speeds in Hz as `hw.cpufrequency` and
+//! `hw.cpufrequency_max` instead. Apple Silicon reports no clock speeds at
+//! all, neither overall nor per performance level.
+
+use std::collections::HashMap;
+
+use super::{CacheSizes, CpuFrequencyReading, CpuTopologyReading, PerfLevelReading};
+
+/// The `name: value` pairs of `sysctl` output.
+fn parse_values(stdout: &str) -> HashMap<&str, &str> {
+    stdout
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect()
+}
+
+pub fn parse_cpu_topology(stdout: &str) -> CpuTopologyReading {
+    let values = parse_values(stdout);
+    let number = |name: &str| values.get(name).and_then(|value| value.parse::<u64>().ok());
+    let cache = |prefix: &str| CacheSizes {
+        l1_instruction_bytes: number(&format!("{}l1icachesize", prefix)),
+        l1_data_bytes: number(&format!("{}l1dcachesize", prefix)),
+        l2_bytes: number(&format!("{}l2cachesize", prefix)),
+        l3_bytes: number(&format!("{}l3cachesize", prefix)),
+    };
+
+    let level_count = number("hw.nperflevels").unwrap_or(0);
+    let perf_levels = (0..level_count)
+        .map_while(|level| {
+            let prefix = format!("hw.perflevel{}.", level);
+            Some(PerfLevelReading {
+                name: values
+                    .get(format!("{}name", prefix).as_str())
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("Level {}", level)),
+                physical_cores: number(&format!("{}physicalcpu", prefix))? as usize,
+                logical_cores: number(&format!("{}logicalcpu", prefix))? as usize,
+                cores_per_l2: number(&format!("{}cpusperl2", prefix)).map(|n| n as usize),
+                cache: cache(&prefix),
+            })
+        })
+        .collect();
+
+    CpuTopologyReading {
+        physical_cores: number("hw.physicalcpu").map(|n| n as usize),
+        logical_cores: number("hw.logicalcpu").map(|n| n as usize),
+        perf_levels,
+        cache: cache("hw."),
+    }
+}
+
+/// The rated and maximum clock speeds. Only Intel Macs report them; the
+/// current clock isn't available from `sysctl` at all.
+pub fn parse_cpu_frequency(stdout: &str) -> CpuFrequencyReading {
+    let values = parse_values(stdout);
+    let mhz = |name: &str| {
+        values
+            .get(name)
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|&hz| hz > 0)
+            .map(|hz| hz / 1_000_000)
+    };
+    CpuFrequencyReading {
+        per_core_mhz: Vec::new(),
+        nominal_mhz: mhz("hw.cpufrequency"),
+        max_mhz: mhz("hw.cpufrequency_max"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apple_silicon_topology() {
+        let topology = parse_cpu_topology(concat!(
+            "hw.ncpu: 10\n",
+            "hw.physicalcpu: 10\n",
+            "hw.logicalcpu: 10\n",
+            "hw.cacheconfig: 10 1 4 0 0 0 0 0 0 0\n",
+            "hw.l1dcachesize: 65536\n",
+            "hw.l2cachesize: 4194304\n",
+            "hw.nperflevels: 2\n",
+            "hw.perflevel0.physicalcpu: 8\n",
+            "hw.perflevel0.logicalcpu: 8\n",
+            "hw.perflevel0.l1dcachesize: 131072\n",
+            "hw.perflevel0.l2cachesize: 16777216\n",
+            "hw.perflevel0.cpusperl2: 4\n",
+            "hw.perflevel0.name: Performance\n",
+            "hw.perflevel1.physicalcpu: 2\n",
+            "hw.perflevel1.logicalcpu: 2\n",
+            "hw.perflevel1.l2cachesize: 4194304\n",
+            "hw.perflevel1.name: Efficiency\n",
+        ));
+        assert_eq!(topology.physical_cores, Some(10));
+        assert_eq!(topology.logical_cores, Some(10));
+        assert_eq!(topology.cache.l1_data_bytes, Some(65536));
+        assert_eq!(topology.cache.l3_bytes, None);
+
+        assert_eq!(topology.perf_levels.len(), 2);
+        let performance = &topology.perf_levels[0];
+        assert_eq!(performance.name, "Performance");
+        assert_eq!(performance.physical_cores, 8);
+        assert_eq!(performance.cores_per_l2, Some(4));
+        assert_eq!(performance.cache.l2_bytes, Some(16777216));
+        let efficiency = &topology.perf_levels[1];
+        assert_eq!(efficiency.name, "Efficiency");
+        assert_eq!(efficiency.logical_cores, 2);
+        assert_eq!(efficiency.cache.l1_data_bytes, None);
+    }
+
+    #[test]
+    fn test_intel_topology() {
+        let topology = parse_cpu_topology(concat!(
+            "hw.physicalcpu: 4\n",
+            "hw.logicalcpu: 8\n",
+            "hw.l2cachesize: 262144\n",
+            "hw.l3cachesize: 8388608\n",
+        ));
+        assert_eq!(topology.physical_cores, Some(4));
+        assert_eq!(topology.logical_cores, Some(8));
+        assert_eq!(topology.cache.l3_bytes, Some(8388608));
+        assert!(topology.perf_levels.is_empty());
+    }
+
+    #[test]
+    fn test_cpu_frequency() {
+        let frequency = parse_cpu_frequency(concat!(
+            "hw.cpufrequency: 2600000000\n",
+            "hw.cpufrequency_min: 2600000000\n",
+            "hw.cpufrequency_max: 4500000000\n",
+        ));
+        assert_eq!(frequency.nominal_mhz, Some(2600));
+        assert_eq!(frequency.max_mhz, Some(4500));
+        assert!(frequency.per_core_mhz.is_empty());
+
+        let frequency = parse_cpu_frequency("hw.nperflevels: 2\n");
+        assert_eq!(frequency.nominal_mhz, None);
+        assert_eq!(frequency.max_mhz, None);
+    }
+}
@@ -0,0 +1,299 @@
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use sysinfo::{
+    CpuRefreshKind, Disks, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System, UpdateKind,
+    MINIMUM_CPU_UPDATE_INTERVAL,
+};
+
+use super::{
+    CpuIdentity, CpuTopologyReading, DiskReading, MemoryReading, ProcessCpuReading,
+    ProcessMemoryReading,
+};
+
+/// Long-lived `sysinfo` state, refreshed incrementally.
+///
+/// `sysinfo` computes CPU usage, for the whole machine and per process, from
+/// the tick delta between two refreshes, so the `System` is kept for the life
+/// of the probe instead of being rebuilt on every call. The first read, and
+/// any read made sooner than the minimum update interval after the previous
+/// refresh, waits out the interval so the delta is meaningful.
+pub struct SystemCache {
+    system: Mutex<CachedSystem>,
+    disks: Mutex<Disks>,
+}
+
+struct CachedSystem {
+    sys: System,
+    cpu_refreshed_at: Option<Instant>,
+    processes_refreshed_at: Option<Instant>,
+    /// When the per-process CPU counters were first read, before the first
+    /// full refresh.
+    processes_primed_at: Option<Instant>,
+}
+
+impl Default for SystemCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemCache {
+    pub fn new() -> Self {
+        Self {
+            system: Mutex::new(CachedSystem {
+                sys: System::new_with_specifics(
+                    RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing().with_cpu_usage()),
+                ),
+                cpu_refreshed_at: None,
+                processes_refreshed_at: None,
+                processes_primed_at: None,
+            }),
+            disks: Mutex::new(Disks::new()),
+        }
+    }
+
+    fn system(&self) -> MutexGuard<'_, CachedSystem> {
+        match self.system.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn memory(&self) -> MemoryReading {
+        let mut state = self.system();
+        state.sys.refresh_memory();
+        MemoryReading {
+            total_bytes: state.sys.total_memory(),
+            used_bytes: state.sys.used_memory(),
+        }
+    }
+
+    pub fn cpu_identity(&self) -> CpuIdentity {
+        let state = self.system();
+        let cpus = state.sys.cpus();
+        CpuIdentity {
+            model_name: cpus
+                .first()
+                .map(|cpu| cpu.brand().to_string())
+                .unwrap_or_else(|| "Unknown".to_string()),
+            core_count: cpus.len(),
+        }
+    }
+
+    /// Core counts only; `sysinfo` knows nothing of performance levels.
+    pub fn cpu_topology(&self) -> CpuTopologyReading {
+        let state = self.system();
+        let logical_cores = state.sys.cpus().len();
+        CpuTopologyReading {
+            physical_cores: state.sys.physical_core_count(),
+            logical_cores: (logical_cores > 0).then_some(logical_cores),
+            ..Default::default()
+        }
+    }
+
+    /// Current clock of every logical core in MHz, where `sysinfo` can
+    /// read it.
+    pub fn per_core_frequency(&self) -> Vec<Option<u64>> {
+        let mut state = self.system();
+        state.sys.refresh_cpu_frequency();
+        state
+            .sys
+            .cpus()
+            .iter()
+            .map(|cpu| Some(cpu.frequency()).filter(|&mhz| mhz > 0))
+            .collect()
+    }
+
+    /// Usage of every logical core, in percent.
+    pub fn per_core_usage(&self) -> Vec<f32> {
+        loop {
+            let mut state = self.system();
+            let remaining = match state.cpu_refreshed_at {
+                Some(at) => MINIMUM_CPU_UPDATE_INTERVAL.saturating_sub(at.elapsed()),
+                None => {
+                    state.sys.refresh_cpu_usage();
+                    state.cpu_refreshed_at = Some(Instant::now());
+                    MINIMUM_CPU_UPDATE_INTERVAL
+                }
+            };
+            if remaining.is_zero() {
+                state.sys.refresh_cpu_usage();
+                state.cpu_refreshed_at = Some(Instant::now());
+                return state.sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+            }
+
+            // Wait without the lock so memory, disk and process reads can go
+            // ahead. Another CPU read may refresh in the meantime, in which
+            // case the interval starts over from there.
+            drop(state);
+            std::thread::sleep(remaining);
+        }
+    }
+
+    /// Lock the system with a fresh process list, reusing the last one when
+    /// it was refreshed within the minimum update interval.
+    fn refreshed_processes(&self) -> MutexGuard<'_, CachedSystem> {
+        let kind = ProcessRefreshKind::nothing()
+            .with_memory()
+            .with_cpu()
+            .with_exe(UpdateKind::OnlyIfNotSet);
+
+        loop {
+            let mut state = self.system();
+            let remaining = match (state.processes_refreshed_at, state.processes_primed_at) {
+                (Some(at), _) if at.elapsed() < MINIMUM_CPU_UPDATE_INTERVAL => return state,
+                (Some(_), _) => Duration::ZERO,
+                (None, Some(at)) => MINIMUM_CPU_UPDATE_INTERVAL.saturating_sub(at.elapsed()),
+                (None, None) => {
+                    // Prime the per-process CPU counters
+                    state
+                        .sys
+                        .refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+                    state.processes_primed_at = Some(Instant::now());
+                    MINIMUM_CPU_UPDATE_INTERVAL
+                }
+            };
+            if remaining.is_zero() {
+                state
+                    .sys
+                    .refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+                state.processes_refreshed_at = Some(Instant::now());
+                return state;
+            }
+
+            // As for CPU usage, wait without the lock
+            drop(state);
+            std::thread::sleep(remaining);
+        }
+    }
+
+    pub fn memory_processes(&self) -> Vec<ProcessMemoryReading> {
+        let state = self.refreshed_processes();
+        state
+            .sys
+            .processes()
+            .values()
+            .map(|process| ProcessMemoryReading {
+                pid: process.pid().as_u32(),
+                command: process
+                    .exe()
+                    .map(|exe| exe.to_string_lossy().to_string())
+                    .unwrap_or_else(|| process.name().to_string_lossy().to_string()),
+                rss_bytes: process.memory(),
+            })
+            .collect()
+    }
+
+    /// Processes by CPU usage, highest first (matching `ps -r`).
+    pub fn cpu_processes(&self) -> Vec<ProcessCpuReading> {
+        let state = self.refreshed_processes();
+        let mut processes: Vec<ProcessCpuReading> = state
+            .sys
+            .processes()
+            .values()
+            .map(|process| ProcessCpuReading {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string_lossy().to_string(),
+                cpu_percentage: process.cpu_usage(),
+            })
+            .collect();
+        processes.sort_by(|a, b| b.cpu_percentage.total_cmp(&a.cpu_percentage));
+        processes
+    }
+
+    pub fn disks(&self) -> Vec<DiskReading> {
+        let mut disks = match self.disks.lock() {
+            Ok(disks) => disks,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        // Picks up newly mounted volumes and drops ejected ones
+        disks.refresh(true);
+
+        disks
+            .iter()
+            .map(|disk| DiskReading {
+                name: disk.name().to_string_lossy().to_string(),
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                total_bytes: disk.total_space(),
+                available_bytes: disk.available_space(),
+                file_system: disk.file_system().to_string_lossy().to_string(),
+                is_removable: disk.is_removable(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_per_core_usage_is_measured() {
+        let cache = SystemCache::new();
+        let first = cache.per_core_usage();
+        assert!(!first.is_empty());
+        assert!(first.iter().all(|u| (0.0..=100.0).contains(u)));
+
+        // Subsequent calls reuse the cached state and keep the core count
+        let second = cache.per_core_usage();
+        assert_eq!(first.len(), second.len());
+        assert_eq!(cache.cpu_identity().core_count, first.len());
+    }
+
+    #[test]
+    fn test_waiting_for_cpu_usage_does_not_block_other_reads() {
+        let cache = Arc::new(SystemCache::new());
+        let reader = Arc::clone(&cache);
+        let usage = std::thread::spawn(move || reader.per_core_usage());
+
+        // Let the CPU read prime its counters and start waiting
+        while cache.system().cpu_refreshed_at.is_none() {
+            std::thread::yield_now();
+        }
+        let started = Instant::now();
+        assert!(cache.memory().total_bytes > 0);
+        assert!(started.elapsed() < MINIMUM_CPU_UPDATE_INTERVAL / 2);
+
+        assert!(!usage.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_priming_the_process_list_does_not_block_other_reads() {
+        let cache = Arc::new(SystemCache::new());
+        let reader = Arc::clone(&cache);
+        let processes = std::thread::spawn(move || reader.memory_processes());
+
+        while cache.system().processes_primed_at.is_none() {
+            std::thread::yield_now();
+        }
+        let started = Instant::now();
+        assert!(cache.memory().total_bytes > 0);
+        assert!(started.elapsed() < MINIMUM_CPU_UPDATE_INTERVAL / 2);
+
+        assert!(!processes.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_process_list_is_reused_within_interval() {
+        let cache = SystemCache::new();
+        let own_pid = std::process::id();
+
+        let processes = cache.memory_processes();
+        assert!(processes.iter().any(|p| p.pid == own_pid));
+        let refreshed_at = cache.system().processes_refreshed_at;
+        assert!(refreshed_at.is_some());
+
+        // A read straight after the first one is served from the same refresh
+        let processes = cache.cpu_processes();
+        assert!(processes.iter().any(|p| p.pid == own_pid));
+        assert_eq!(cache.system().processes_refreshed_at, refreshed_at);
+    }
+
+    #[test]
+    fn test_memory_is_refreshed() {
+        let memory = SystemCache::new().memory();
+        assert!(memory.total_bytes > 0);
+        assert!(memory.used_bytes <= memory.total_bytes);
+    }
+}
@@ -0,0 +1,302 @@
+//! Background sampling of the monitors.
+//!
+//! Each metric is sampled on its own thread at its own interval and pushed to
+//! every window as a `metrics://<metric>` event, so the UI no longer has to
+//! poll. Sampling runs for the lifetime of the app, including while the
+//! popover is hidden.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Runtime, State};
+
+use crate::error::MonitorError;
+use crate::history::battery::{BatteryHistory, BatterySample, SharedBatteryHistory};
+use crate::history::cpu::{CpuHistory, SharedCpuHistory};
+use crate::history::health::{HealthHistory, HealthSample, SharedHealthHistory};
+use crate::monitors::{battery, cpu, disk, ram};
+use crate::platform::{SharedProbe, SystemProbe};
+
+/// Sampler shared with the commands that reconfigure it.
+pub type SharedSampler = Arc<Sampler>;
+
+/// Shortest interval a metric can be sampled at.
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricKind {
+    Ram,
+    Cpu,
+    Disk,
+    Battery,
+}
+
+impl MetricKind {
+    pub const ALL: [MetricKind; 4] = [
+        MetricKind::Ram,
+        MetricKind::Cpu,
+        MetricKind::Disk,
+        MetricKind::Battery,
+    ];
+
+    /// Name of the event samples of this metric are emitted as.
+    pub fn event(&self) -> &'static str {
+        match self {
+            MetricKind::Ram => "metrics://ram",
+            MetricKind::Cpu => "metrics://cpu",
+            MetricKind::Disk => "metrics://disk",
+            MetricKind::Battery => "metrics://battery",
+        }
+    }
+
+    fn default_interval(&self) -> Duration {
+        match self {
+            MetricKind::Ram | MetricKind::Cpu => Duration::from_secs(3),
+            MetricKind::Battery => Duration::from_secs(10),
+            MetricKind::Disk => Duration::from_secs(30),
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Payload of a metric event: the reading, or the error that prevented it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample<T> {
+    /// Milliseconds since the Unix epoch at which the reading completed
+    pub timestamp_ms: u64,
+    pub data: Option<T>,
+    pub error: Option<MonitorError>,
+}
+
+impl<T> Sample<T> {
+    pub fn take(read: impl FnOnce() -> Result<T, MonitorError>) -> Self {
+        // After the read, which can take a while, e.g. the CPU usage waits
+        // between two refreshes
+        let result = read();
+        let timestamp_ms = now_ms();
+        match result {
+            Ok(data) => Sample {
+                timestamp_ms,
+                data: Some(data),
+                error: None,
+            },
+            Err(error) => Sample {
+                timestamp_ms,
+                data: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Per-metric sampling intervals, shared with the sampling threads.
+pub struct Sampler {
+    intervals: Mutex<[Duration; 4]>,
+    changed: Condvar,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self {
+            intervals: Mutex::new(MetricKind::ALL.map(|kind| kind.default_interval())),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, [Duration; 4]> {
+        match self.intervals.lock() {
+            Ok(intervals) => intervals,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn interval(&self, kind: MetricKind) -> Duration {
+        self.lock()[kind.index()]
+    }
+
+    /// Change how often `kind` is sampled. A sampler already waiting picks
+    /// the new interval up immediately.
+    pub fn set_interval(&self, kind: MetricKind, interval: Duration) -> Result<(), MonitorError> {
+        if interval < MIN_INTERVAL {
+            return Err(MonitorError::InvalidArgument(format!(
+                "Sampling interval must be at least {} ms",
+                MIN_INTERVAL.as_millis()
+            )));
+        }
+        self.lock()[kind.index()] = interval;
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// Block until the interval for `kind` has elapsed since `since`.
+    fn wait(&self, kind: MetricKind, since: Instant) {
+        let mut intervals = self.lock();
+        loop {
+            let elapsed = since.elapsed();
+            let interval = intervals[kind.index()];
+            if elapsed >= interval {
+                return;
+            }
+            intervals = match self.changed.wait_timeout(intervals, interval - elapsed) {
+                Ok((intervals, _)) => intervals,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+    }
+}
+
+/// Start one sampling thread per metric. CPU samples are also recorded to
+/// the CPU history, and battery samples to the battery and health
+/// histories.
+pub fn start<R: Runtime>(
+    app: AppHandle<R>,
+    probe: SharedProbe,
+    sampler: SharedSampler,
+    cpu_history: SharedCpuHistory,
+    battery_history: SharedBatteryHistory,
+    health_history: SharedHealthHistory,
+) {
+    for kind in MetricKind::ALL {
+        let app = app.clone();
+        let probe = Arc::clone(&probe);
+        let sampler = Arc::clone(&sampler);
+        let cpu_history = Arc::clone(&cpu_history);
+        let battery_history = Arc::clone(&battery_history);
+        let health_history = Arc::clone(&health_history);
+        std::thread::spawn(move || loop {
+            let started = Instant::now();
+            // A failed emit is dropped; the next sample goes out on schedule
+            let _ = emit_sample(
+                &app,
+                kind,
+                sampler.interval(kind),
+                probe.as_ref(),
+                &cpu_history,
+                &battery_history,
+                &health_history,
+            );
+            sampler.wait(kind, started);
+        });
+    }
+}
+
+fn emit_sample<R: Runtime>(
+    app: &AppHandle<R>,
+    kind: MetricKind,
+    interval: Duration,
+    probe: &dyn SystemProbe,
+    cpu_history: &CpuHistory,
+    battery_history: &BatteryHistory,
+    health_history: &HealthHistory,
+) -> tauri::Result<()> {
+    match kind {
+        MetricKind::Ram => app.emit(kind.event(), Sample::take(|| ram::ram_info(probe))),
+        MetricKind::Cpu => {
+            let sample = Sample::take(|| cpu::cpu_info(probe));
+            if let Some(info) = sample.data.as_ref() {
+                cpu_history.record(sample.timestamp_ms, interval, info);
+            }
+            app.emit(kind.event(), sample)
+        }
+        MetricKind::Disk => app.emit(kind.event(), Sample::take(|| disk::disk_info(probe))),
+        MetricKind::Battery => {
+            let mut sample = Sample::take(|| battery::battery_info(probe));
+            if let Some(info) = sample.data.as_mut() {
+                battery_history.record(BatterySample::from_info(sample.timestamp_ms, info));
+                health_history.record(HealthSample::from_info(sample.timestamp_ms, info));
+                info.time_estimate =
+                    battery_history.estimate_time_remaining(sample.timestamp_ms, info);
+            }
+            app.emit(kind.event(), sample)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn set_sampling_interval(
+    sampler: State<'_, SharedSampler>,
+    metric: MetricKind,
+    interval_ms: u64,
+) -> Result<(), MonitorError> {
+    sampler.set_interval(metric, Duration::from_millis(interval_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_serializes_data_or_error() {
+        let ok = serde_json::to_value(Sample::take(|| Ok(42))).unwrap();
+        assert_eq!(ok["data"], 42);
+        assert!(ok["error"].is_null());
+        assert!(ok["timestamp_ms"].as_u64().unwrap() > 0);
+
+        let err =
+            serde_json::to_value(Sample::<u32>::take(|| Err(MonitorError::NoBattery))).unwrap();
+        assert!(err["data"].is_null());
+        assert_eq!(err["error"]["code"], "NoBattery");
+    }
+
+    #[test]
+    fn test_metric_names() {
+        assert_eq!(MetricKind::Cpu.event(), "metrics://cpu");
+        let kind: MetricKind = serde_json::from_str("\"battery\"").unwrap();
+        assert_eq!(kind, MetricKind::Battery);
+    }
+
+    #[test]
+    fn test_set_interval_rejects_too_short() {
+        let sampler = Sampler::new();
+        assert_eq!(
+            sampler
+                .set_interval(MetricKind::Ram, Duration::from_millis(10))
+                .unwrap_err()
+                .code(),
+            "InvalidArgument"
+        );
+        assert_eq!(sampler.interval(MetricKind::Ram), Duration::from_secs(3));
+
+        sampler
+            .set_interval(MetricKind::Ram, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(sampler.interval(MetricKind::Ram), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_wait_picks_up_shorter_interval() {
+        let sampler = Arc::new(Sampler::new());
+        let started = Instant::now();
+
+        let waiter = {
+            let sampler = Arc::clone(&sampler);
+            std::thread::spawn(move || sampler.wait(MetricKind::Disk, started))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        sampler
+            .set_interval(MetricKind::Disk, MIN_INTERVAL)
+            .unwrap();
+        waiter.join().unwrap();
+
+        let elapsed = started.elapsed();
+        assert!(elapsed >= MIN_INTERVAL);
+        assert!(elapsed < MetricKind::Disk.default_interval());
+    }
+}
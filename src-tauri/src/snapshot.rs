@@ -0,0 +1,157 @@
+//! Every monitor read in one call.
+//!
+//! The dashboard used to issue one command per widget, each spawning its own
+//! tools. A snapshot reads all sections concurrently from the same probe, so
+//! the widgets it feeds are consistent with each other.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::State;
+
+use crate::error::MonitorError;
+use crate::history::battery::SharedBatteryHistory;
+use crate::monitors::battery::{self, BatteryInfo};
+use crate::monitors::cpu::{self, CpuInfo, ProcessCpuInfo};
+use crate::monitors::disk::{self, DisksOverview};
+use crate::monitors::ram::{self, ProcessMemoryInfo, RamInfo};
+use crate::platform::{SharedProbe, SystemProbe};
+use crate::sampler::Sample;
+
+/// Number of the last snapshot taken, shared by every window.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Default number of processes in each top-processes section.
+const DEFAULT_PROCESS_COUNT: usize = 5;
+
+/// A snapshot section fails on its own: the others still carry data.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemSnapshot {
+    /// Increases with every snapshot, so stale responses can be discarded
+    pub sequence: u64,
+    pub ram: Sample<RamInfo>,
+    pub cpu: Sample<CpuInfo>,
+    pub disk: Sample<DisksOverview>,
+    pub battery: Sample<BatteryInfo>,
+    pub top_memory_processes: Sample<Vec<ProcessMemoryInfo>>,
+    pub top_cpu_processes: Sample<Vec<ProcessCpuInfo>>,
+    pub uptime_seconds: Sample<u64>,
+}
+
+#[tauri::command(async)]
+pub fn get_system_snapshot(
+    probe: State<'_, SharedProbe>,
+    battery_history: State<'_, SharedBatteryHistory>,
+    process_count: Option<usize>,
+) -> SystemSnapshot {
+    let mut snapshot = system_snapshot(&**probe, process_count);
+    let timestamp_ms = snapshot.battery.timestamp_ms;
+    if let Some(info) = snapshot.battery.data.as_mut() {
+        info.time_estimate = battery_history.estimate_time_remaining(timestamp_ms, info);
+    }
+    snapshot
+}
+
+pub fn system_snapshot(probe: &dyn SystemProbe, process_count: Option<usize>) -> SystemSnapshot {
+    let count = Some(process_count.unwrap_or(DEFAULT_PROCESS_COUNT));
+
+    // CPU and process sections wait out a sampling interval, so read every
+    // section at once rather than one after another
+    std::thread::scope(|scope| {
+        let cpu = scope.spawn(|| Sample::take(|| cpu::cpu_info(probe)));
+        let top_cpu = scope.spawn(|| Sample::take(|| cpu::top_cpu_processes(probe, count)));
+        let top_memory = scope.spawn(|| Sample::take(|| ram::top_memory_processes(probe, count)));
+        let battery = scope.spawn(|| Sample::take(|| battery::battery_info(probe)));
+
+        let ram = Sample::take(|| ram::ram_info(probe));
+        let disk = Sample::take(|| disk::disk_info(probe));
+        let uptime_seconds = Sample::take(|| Ok(probe.uptime()));
+
+        SystemSnapshot {
+            sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed) + 1,
+            ram,
+            cpu: join(cpu),
+            disk,
+            battery: join(battery),
+            top_memory_processes: join(top_memory),
+            top_cpu_processes: join(top_cpu),
+            uptime_seconds,
+        }
+    })
+}
+
+fn join<T>(handle: std::thread::ScopedJoinHandle<'_, Sample<T>>) -> Sample<T> {
+    handle.join().unwrap_or_else(|_| {
+        Sample::take(|| {
+            Err(MonitorError::Internal(
+                "Snapshot section panicked".to_string(),
+            ))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::testing::FakeProbe;
+    use crate::platform::{
+        CpuUsageReading, MemoryReading, ProcessCpuReading, ProcessMemoryReading,
+    };
+
+    fn probe() -> FakeProbe {
+        FakeProbe {
+            memory: MemoryReading {
+                total_bytes: 16 * 1024 * 1024 * 1024,
+                used_bytes: 8 * 1024 * 1024 * 1024,
+            },
+            memory_processes: (1..=8)
+                .map(|pid| ProcessMemoryReading {
+                    pid,
+                    command: format!("/usr/bin/proc{}", pid),
+                    rss_bytes: pid as u64 * 1024,
+                })
+                .collect(),
+            cpu_processes: vec![ProcessCpuReading {
+                pid: 1,
+                name: "kernel_task".to_string(),
+                cpu_percentage: 12.5,
+            }],
+            cpu_usage: Some(CpuUsageReading {
+                total_percentage: 20.0,
+                per_core: vec![20.0; 4],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_snapshot_sections_fail_independently() {
+        // FakeProbe has no battery
+        let snapshot = system_snapshot(&probe(), None);
+
+        assert_eq!(snapshot.ram.data.unwrap().used_percentage, 50.0);
+        assert_eq!(snapshot.cpu.data.unwrap().total_usage_percentage, 20.0);
+        assert_eq!(snapshot.top_cpu_processes.data.unwrap().len(), 1);
+        assert_eq!(snapshot.uptime_seconds.data, Some(3600));
+        assert!(snapshot.battery.data.is_none());
+        assert_eq!(snapshot.battery.error.unwrap().code(), "NoBattery");
+    }
+
+    #[test]
+    fn test_snapshot_limits_processes() {
+        let snapshot = system_snapshot(&probe(), None);
+        let processes = snapshot.top_memory_processes.data.unwrap();
+        assert_eq!(processes.len(), DEFAULT_PROCESS_COUNT);
+        assert_eq!(processes[0].pid, 8);
+
+        let snapshot = system_snapshot(&probe(), Some(2));
+        assert_eq!(snapshot.top_memory_processes.data.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_sequence_increases() {
+        let first = system_snapshot(&probe(), None).sequence;
+        let second = system_snapshot(&probe(), None).sequence;
+        assert!(second > first);
+    }
+}
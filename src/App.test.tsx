@@ -3,65 +3,115 @@ import { describe, it, expect, vi, beforeEach } from "vitest";
 import App from "./App";
 
 // Mock Tauri APIs
-vi.mock("@tauri-apps/api/core", () => ({
-  invoke: vi.fn().mockImplementation((cmd: string) => {
-    switch (cmd) {
-      case "get_ram_info":
-        return Promise.resolve({
-          total_bytes: 17179869184,
-          used_bytes: 10737418240,
-          available_bytes: 6442450944,
-          used_percentage: 62.5,
-          pressure_level: "normal",
-        });
-      case "get_cpu_info":
-        return Promise.resolve({
-          model_name: "Apple M3",
-          total_cores: 8,
-          total_usage_percentage: 25.0,
-          per_core_usage: [20, 30, 25, 22, 15, 18, 20, 25],
-          load_average: {
-            one_minute: 1.5,
-            five_minutes: 1.2,
-            fifteen_minutes: 1.0,
-          },
-        });
-      case "get_battery_info":
-        return Promise.resolve({
-          percentage: 85,
-          is_charging: false,
-          is_plugged_in: false,
-          power_source: "Battery",
-          condition: "Normal",
-          max_capacity_percentage: 96,
-          cycle_count: 150,
-          time_to_full_minutes: null,
-          time_to_empty_minutes: 180,
-          temperature_celsius: 32.5,
-          voltage_volts: 12.4,
-        });
-      case "get_disk_info":
-        return Promise.resolve({
-          primary: {
-            name: "Macintosh HD",
-            mount_point: "/",
-            total_bytes: 500107862016,
-            available_bytes: 150000000000,
-            used_bytes: 350107862016,
-            used_percentage: 70,
-            file_system: "APFS",
-            is_removable: false,
-          },
-          all_disks: [],
-          total_space_bytes: 500107862016,
-          total_available_bytes: 150000000000,
-          total_used_bytes: 350107862016,
-          total_used_percentage: 70,
-        });
-      default:
-        return Promise.resolve(null);
-    }
-  }),
+vi.mock("@tauri-apps/api/core", () => {
+  const ram = {
+    total_bytes: 17179869184,
+    used_bytes: 10737418240,
+    available_bytes: 6442450944,
+    used_percentage: 62.5,
+    pressure_level: "normal",
+  };
+  const cpu = {
+    model_name: "Apple M3",
+    total_cores: 8,
+    physical_cores: null,
+    performance_cores: null,
+    efficiency_cores: null,
+    clusters: [],
+    cache: {
+      l1_instruction_bytes: null,
+      l1_data_bytes: null,
+      l2_bytes: null,
+      l3_bytes: null,
+    },
+    total_usage_percentage: 25.0,
+    is_estimate: false,
+    breakdown: null,
+    per_core_usage: [
+      { usage_percentage: 20, kind: null, frequency_mhz: null },
+      { usage_percentage: 30, kind: null, frequency_mhz: null },
+      { usage_percentage: 25, kind: null, frequency_mhz: null },
+      { usage_percentage: 22, kind: null, frequency_mhz: null },
+      { usage_percentage: 15, kind: null, frequency_mhz: null },
+      { usage_percentage: 18, kind: null, frequency_mhz: null },
+      { usage_percentage: 20, kind: null, frequency_mhz: null },
+      { usage_percentage: 25, kind: null, frequency_mhz: null },
+    ],
+    load_average: {
+      one_minute: 1.5,
+      five_minutes: 1.2,
+      fifteen_minutes: 1.0,
+    },
+    frequency: {
+      current_mhz: null,
+      nominal_mhz: null,
+      max_mhz: null,
+    },
+    thermal: null,
+  };
+  const battery = {
+    percentage: 85,
+    is_charging: false,
+    is_plugged_in: false,
+    power_source: "Battery",
+    condition: "Normal",
+    max_capacity_percentage: 96,
+    cycle_count: 150,
+    time_to_full_minutes: null,
+    time_to_empty_minutes: 180,
+    temperature_celsius: 32.5,
+    voltage_volts: 12.4,
+  };
+  const disk = {
+    primary: {
+      name: "Macintosh HD",
+      mount_point: "/",
+      total_bytes: 500107862016,
+      available_bytes: 150000000000,
+      used_bytes: 350107862016,
+      used_percentage: 70,
+      file_system: "APFS",
+      is_removable: false,
+    },
+    all_disks: [],
+    total_space_bytes: 500107862016,
+    total_available_bytes: 150000000000,
+    total_used_bytes: 350107862016,
+    total_used_percentage: 70,
+  };
+  const sample = <T,>(data: T) => ({ timestamp_ms: 0, data, error: null });
+
+  return {
+    invoke: vi.fn().mockImplementation((cmd: string) => {
+      switch (cmd) {
+        case "get_ram_info":
+          return Promise.resolve(ram);
+        case "get_cpu_info":
+          return Promise.resolve(cpu);
+        case "get_battery_info":
+          return Promise.resolve(battery);
+        case "get_disk_info":
+          return Promise.resolve(disk);
+        case "get_system_snapshot":
+          return Promise.resolve({
+            sequence: 1,
+            ram: sample(ram),
+            cpu: sample(cpu),
+            disk: sample(disk),
+            battery: sample(battery),
+            top_memory_processes: sample([]),
+            top_cpu_processes: sample([]),
+            uptime_seconds: sample(3600),
+          });
+        default:
+          return Promise.resolve(null);
+      }
+    }),
+  };
+});
+
+vi.mock("@tauri-apps/api/event", () => ({
+  listen: vi.fn(() => Promise.resolve(() => {})),
 }));
 
 vi.mock("@tauri-apps/api/window", () => ({
@@ -3,6 +3,7 @@ import { motion, AnimatePresence } from "framer-motion";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { useSystemStore } from "./store/systemStore";
 import { updateTrayStatus } from "./lib/tauri";
+import { useMetricEvents } from "./hooks/useMetricEvents";
 import { Dashboard } from "./features/dashboard";
 import { MemoryDetail } from "./features/memory";
 import { StorageDetail } from "./features/storage";
@@ -73,15 +74,15 @@ function App() {
     setShowSetup(false);
   };
 
-  // Initial data fetch and polling (only when not in setup)
+  // Initial data fetch, then live updates from the backend sampler
+  // (only when not in setup)
   useEffect(() => {
     if (showSetup) return;
-
     refreshAll();
-    const interval = setInterval(refreshAll, 3000);
-    return () => clearInterval(interval);
   }, [refreshAll, showSetup]);
 
+  useMetricEvents(!showSetup);
+
   // Update tray icon when status changes
   useEffect(() => {
     if (showSetup) return;
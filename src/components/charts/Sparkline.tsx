@@ -0,0 +1,59 @@
+interface SparklineProps {
+  /** Oldest first; `null` leaves a gap in the line */
+  values: (number | null)[];
+  /** Defaults to the largest value */
+  maxValue?: number;
+  height?: number;
+  color?: string;
+  label?: string;
+}
+
+export function Sparkline({
+  values,
+  maxValue,
+  height = 40,
+  color = "#8b5cf6",
+  label,
+}: SparklineProps) {
+  const known = values.filter((value): value is number => value !== null);
+  const max = maxValue ?? Math.max(...known, 0);
+  const step = values.length > 1 ? 100 / (values.length - 1) : 0;
+
+  // One polyline per run of known values
+  const segments: string[][] = [];
+  let segment: string[] = [];
+  values.forEach((value, index) => {
+    if (value === null) {
+      if (segment.length > 0) segments.push(segment);
+      segment = [];
+      return;
+    }
+    const normalizedValue = max > 0 ? Math.min(Math.max(value, 0), max) : 0;
+    const y = height - (max > 0 ? (normalizedValue / max) * height : 0);
+    segment.push(`${(index * step).toFixed(2)},${y.toFixed(2)}`);
+  });
+  if (segment.length > 0) segments.push(segment);
+
+  return (
+    <svg
+      width="100%"
+      height={height}
+      viewBox={`0 0 100 ${height}`}
+      preserveAspectRatio="none"
+      role="img"
+      aria-label={label}
+      className="overflow-visible"
+    >
+      {segments.map((points, index) => (
+        <polyline
+          key={index}
+          points={points.join(" ")}
+          fill="none"
+          stroke={color}
+          strokeWidth={1.5}
+          vectorEffect="non-scaling-stroke"
+        />
+      ))}
+    </svg>
+  );
+}
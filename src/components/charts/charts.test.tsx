@@ -4,6 +4,7 @@ import { BarChart } from "./BarChart";
 import { CircularProgress } from "./CircularProgress";
 import { DonutChart } from "./DonutChart";
 import { GaugeChart } from "./GaugeChart";
+import { Sparkline } from "./Sparkline";
 
 describe("BarChart", () => {
   it("renders bars for each value", () => {
@@ -207,3 +208,27 @@ describe("GaugeChart", () => {
     expect(svg?.getAttribute("width")).toBe("200");
   });
 });
+
+describe("Sparkline", () => {
+  it("draws one line through the values", () => {
+    const { container } = render(<Sparkline values={[0, 50, 100]} />);
+    const lines = container.querySelectorAll("polyline");
+    expect(lines.length).toBe(1);
+    expect(lines[0].getAttribute("points")).toBe(
+      "0.00,40.00 50.00,20.00 100.00,0.00"
+    );
+  });
+
+  it("leaves gaps for missing values", () => {
+    const { container } = render(
+      <Sparkline values={[10, 20, null, 30, 40]} maxValue={100} />
+    );
+    expect(container.querySelectorAll("polyline").length).toBe(2);
+  });
+
+  it("renders nothing to draw without values", () => {
+    const { container } = render(<Sparkline values={[]} label="Empty" />);
+    expect(screen.getByRole("img", { name: "Empty" })).toBeInTheDocument();
+    expect(container.querySelectorAll("polyline").length).toBe(0);
+  });
+});
@@ -2,3 +2,4 @@ export { CircularProgress } from "./CircularProgress";
 export { BarChart } from "./BarChart";
 export { GaugeChart } from "./GaugeChart";
 export { DonutChart } from "./DonutChart";
+export { Sparkline } from "./Sparkline";
@@ -1,16 +1,39 @@
-import { render, screen, fireEvent } from "@testing-library/react";
+import { render, screen, fireEvent, waitFor } from "@testing-library/react";
 import { describe, it, expect, vi, beforeEach } from "vitest";
 import { BatteryDetail } from "./BatteryDetail";
 
 // Mock Tauri APIs
 vi.mock("../../lib/tauri", () => ({
   openEnergySettings: vi.fn().mockResolvedValue(undefined),
+  getSleepAssertions: vi.fn().mockResolvedValue([
+    {
+      pid: 2287,
+      process_name: "Dropbox Helper",
+      assertion_type: "PreventUserIdleSystemSleep",
+      name: "com.dropbox.sync",
+      held_seconds: 50811,
+      prevents_display_sleep: false,
+    },
+  ]),
+  forceQuitProcess: vi.fn().mockResolvedValue({ success: true, message: "" }),
+  setLowPowerModeWithAdmin: vi.fn().mockResolvedValue({
+    success: true,
+    message: "Low Power Mode enabled on battery",
+  }),
 }));
 
+// Mock window.confirm
+window.confirm = vi.fn().mockReturnValue(true);
+
+// Per-test changes to the battery below
+const batteryOverrides = vi.hoisted(() => ({}) as Record<string, unknown>);
+
 // Mock Zustand store - use 60 minutes for easy formatting (1h)
+const mockRefreshAll = vi.fn();
 vi.mock("../../store/systemStore", () => ({
   useSystemStore: vi.fn((selector) => {
     const state = {
+      refreshAll: mockRefreshAll,
       battery: {
         percentage: 85,
         is_charging: false,
@@ -23,6 +46,28 @@ vi.mock("../../store/systemStore", () => ({
         time_to_empty_minutes: 60,
         temperature_celsius: 32.5,
         voltage_volts: 12.4,
+        amperage_ma: -914,
+        power_draw_watts: 11.546,
+        cell_voltages_mv: [4210, 4211, 4185],
+        cell_imbalance_mv: 26,
+        adapter: {
+          watts: 30,
+          name: "30W USB-C Power Adapter",
+          description: "pd charger",
+          family: "0xe000400a",
+          voltage_volts: 15,
+          current_amps: 2,
+          is_wireless: false,
+          is_underpowered: true,
+        },
+        time_estimate: {
+          minutes: 75,
+          is_charging: false,
+          confidence: "high",
+          rate_percentage_per_hour: -68,
+        },
+        low_power_mode: false,
+        ...batteryOverrides,
       },
     };
     return selector(state);
@@ -32,6 +77,9 @@ vi.mock("../../store/systemStore", () => ({
 describe("BatteryDetail", () => {
   beforeEach(() => {
     vi.clearAllMocks();
+    for (const key of Object.keys(batteryOverrides)) {
+      delete batteryOverrides[key];
+    }
   });
 
   it("renders the header", () => {
@@ -53,6 +101,31 @@ describe("BatteryDetail", () => {
     expect(screen.getByText("Battery Status")).toBeInTheDocument();
   });
 
+  it("prefers the time remaining reported by macOS", () => {
+    render(<BatteryDetail />);
+    expect(screen.getByText("1h remaining")).toBeInTheDocument();
+  });
+
+  it("falls back to the estimated time remaining", () => {
+    batteryOverrides.time_to_empty_minutes = null;
+    render(<BatteryDetail />);
+    expect(screen.getByText("~1h 15m remaining")).toBeInTheDocument();
+  });
+
+  it("flags low confidence estimates", () => {
+    batteryOverrides.time_to_empty_minutes = null;
+    batteryOverrides.time_estimate = {
+      minutes: 200,
+      is_charging: false,
+      confidence: "low",
+      rate_percentage_per_hour: -25,
+    };
+    render(<BatteryDetail />);
+    expect(
+      screen.getByText("~3h 20m remaining (rough estimate)")
+    ).toBeInTheDocument();
+  });
+
   it("renders health and cycles stats", () => {
     render(<BatteryDetail />);
     expect(screen.getByText("Health")).toBeInTheDocument();
@@ -77,6 +150,68 @@ describe("BatteryDetail", () => {
     expect(screen.getByText("12.40 V")).toBeInTheDocument();
   });
 
+  it("renders power draw and cell voltages", () => {
+    render(<BatteryDetail />);
+    expect(screen.getByText("Power")).toBeInTheDocument();
+    expect(screen.getByText("11.5 W")).toBeInTheDocument();
+    expect(screen.getByText("-914 mA")).toBeInTheDocument();
+    expect(screen.getByText("Cell 3")).toBeInTheDocument();
+    expect(screen.getByText("4.185 V")).toBeInTheDocument();
+    expect(screen.getByText("26 mV")).toBeInTheDocument();
+  });
+
+  it("renders adapter details and warns when underpowered", () => {
+    render(<BatteryDetail />);
+    expect(screen.getByText("Power Adapter")).toBeInTheDocument();
+    expect(screen.getByText("30W USB-C Power Adapter")).toBeInTheDocument();
+    expect(screen.getByText("30 W")).toBeInTheDocument();
+    expect(screen.getByText("15.0 V / 2.00 A")).toBeInTheDocument();
+    expect(screen.getByText(/can't keep up with your Mac/)).toBeInTheDocument();
+  });
+
+  it("turns on Low Power Mode for the source in use", async () => {
+    const { setLowPowerModeWithAdmin } = await import("../../lib/tauri");
+    render(<BatteryDetail />);
+    expect(screen.getByText("Off while on battery")).toBeInTheDocument();
+
+    fireEvent.click(screen.getByText("Turn On"));
+
+    expect(setLowPowerModeWithAdmin).toHaveBeenCalledWith("battery", true);
+    await waitFor(() => {
+      expect(
+        screen.getByText("Low Power Mode enabled on battery")
+      ).toBeInTheDocument();
+    });
+    expect(mockRefreshAll).toHaveBeenCalled();
+  });
+
+  it("hides Low Power Mode where the platform has none", () => {
+    batteryOverrides.low_power_mode = null;
+    render(<BatteryDetail />);
+    expect(screen.queryByText("Low Power Mode")).not.toBeInTheDocument();
+  });
+
+  it("lists processes keeping the Mac awake", async () => {
+    render(<BatteryDetail />);
+    await waitFor(() => {
+      expect(screen.getByText("Dropbox Helper")).toBeInTheDocument();
+    });
+    expect(screen.getByText("Prevents sleep for 14h 7m")).toBeInTheDocument();
+  });
+
+  it("force quits a process keeping the Mac awake", async () => {
+    const { forceQuitProcess } = await import("../../lib/tauri");
+    render(<BatteryDetail />);
+    await waitFor(() => {
+      expect(screen.getByText("Dropbox Helper")).toBeInTheDocument();
+    });
+
+    fireEvent.click(screen.getByTitle("Force Quit"));
+
+    expect(window.confirm).toHaveBeenCalled();
+    expect(forceQuitProcess).toHaveBeenCalledWith(2287);
+  });
+
   it("renders Open Energy Settings button", () => {
     render(<BatteryDetail />);
     expect(screen.getByText("Open Energy Settings")).toBeInTheDocument();
@@ -1,8 +1,15 @@
+import { useEffect, useState } from "react";
 import { BackButton, StatCard } from "../../components/ui";
 import { CircularProgress } from "../../components/charts";
 import { useSystemStore } from "../../store/systemStore";
 import { formatTimeRemaining } from "../../lib/formatters";
-import { openEnergySettings } from "../../lib/tauri";
+import {
+  forceQuitProcess,
+  getSleepAssertions,
+  openEnergySettings,
+  setLowPowerModeWithAdmin,
+} from "../../lib/tauri";
+import type { SleepAssertion } from "../../types";
 
 // Icons
 function HeartIcon() {
@@ -46,6 +53,19 @@ function RefreshIcon() {
 
 export function BatteryDetail() {
   const battery = useSystemStore((s) => s.battery);
+  const refreshAll = useSystemStore((s) => s.refreshAll);
+  const [assertions, setAssertions] = useState<SleepAssertion[]>([]);
+  const [quitResult, setQuitResult] = useState<string | null>(null);
+  const [switchingPowerMode, setSwitchingPowerMode] = useState(false);
+  const [powerModeResult, setPowerModeResult] = useState<string | null>(null);
+
+  const loadAssertions = () => {
+    getSleepAssertions().then(setAssertions).catch(console.error);
+  };
+
+  useEffect(() => {
+    loadAssertions();
+  }, []);
 
   const percentage = battery?.percentage ?? 0;
   const isCharging = battery?.is_charging ?? false;
@@ -66,6 +86,23 @@ export function BatteryDetail() {
         ? "text-health-warning"
         : "text-health-critical";
 
+  // Prefer the time macOS reports, then our own estimate
+  const reportedMinutes = isCharging
+    ? battery?.time_to_full_minutes
+    : battery?.time_to_empty_minutes;
+  const estimate =
+    battery?.time_estimate?.is_charging === isCharging
+      ? battery.time_estimate
+      : null;
+  const direction = isCharging ? "to full" : "remaining";
+  let timeRemaining = "Calculating...";
+  if (reportedMinutes != null) {
+    timeRemaining = `${formatTimeRemaining(reportedMinutes)} ${direction}`;
+  } else if (estimate) {
+    timeRemaining = `~${formatTimeRemaining(estimate.minutes)} ${direction}`;
+    if (estimate.confidence === "low") timeRemaining += " (rough estimate)";
+  }
+
   const handleOpenEnergySettings = async () => {
     try {
       await openEnergySettings();
@@ -74,6 +111,47 @@ export function BatteryDetail() {
     }
   };
 
+  // Low Power Mode is set separately for battery and power adapter, switch
+  // the one for the source in use
+  const handleToggleLowPowerMode = async () => {
+    if (!battery || battery.low_power_mode === null) return;
+
+    setSwitchingPowerMode(true);
+    try {
+      const result = await setLowPowerModeWithAdmin(
+        battery.is_plugged_in ? "ac" : "battery",
+        !battery.low_power_mode
+      );
+      setPowerModeResult(result.message);
+      if (result.success) await refreshAll();
+    } catch (error) {
+      setPowerModeResult("Failed to change Low Power Mode");
+      console.error(error);
+    } finally {
+      setSwitchingPowerMode(false);
+      setTimeout(() => setPowerModeResult(null), 3000);
+    }
+  };
+
+  const handleForceQuit = async (pid: number, name: string) => {
+    if (!confirm(`Force quit "${name}"?`)) return;
+
+    try {
+      const result = await forceQuitProcess(pid);
+      if (result.success) {
+        setQuitResult(`Terminated ${name}`);
+        setTimeout(loadAssertions, 500);
+      } else {
+        setQuitResult(result.message);
+      }
+    } catch (error) {
+      setQuitResult("Failed to terminate process");
+      console.error(error);
+    } finally {
+      setTimeout(() => setQuitResult(null), 3000);
+    }
+  };
+
   if (!battery) {
     return (
       <div className="flex flex-col h-full">
@@ -128,15 +206,7 @@ export function BatteryDetail() {
               )}
               {isCharging ? "Charging" : battery.power_source}
             </div>
-            <div className="text-sm text-white/40">
-              {isCharging
-                ? formatTimeRemaining(battery.time_to_full_minutes)
-                  ? `${formatTimeRemaining(battery.time_to_full_minutes)} to full`
-                  : "Calculating..."
-                : formatTimeRemaining(battery.time_to_empty_minutes)
-                  ? `${formatTimeRemaining(battery.time_to_empty_minutes)} remaining`
-                  : "Calculating..."}
-            </div>
+            <div className="text-sm text-white/40">{timeRemaining}</div>
           </div>
         </div>
 
@@ -190,6 +260,167 @@ export function BatteryDetail() {
           </div>
         </div>
 
+        {/* Low Power Mode */}
+        {battery.low_power_mode !== null && (
+          <div className="card">
+            <div className="flex justify-between items-center">
+              <div>
+                <div className="text-sm font-medium">Low Power Mode</div>
+                <div className="text-xs text-white/40">
+                  {battery.low_power_mode ? "On" : "Off"} while on{" "}
+                  {battery.is_plugged_in ? "power adapter" : "battery"}
+                </div>
+              </div>
+              <button
+                onClick={handleToggleLowPowerMode}
+                disabled={switchingPowerMode}
+                className="btn-primary px-3 py-1 text-sm"
+              >
+                {battery.low_power_mode ? "Turn Off" : "Turn On"}
+              </button>
+            </div>
+            {powerModeResult && (
+              <div className="text-sm text-white/60 mt-2">
+                {powerModeResult}
+              </div>
+            )}
+          </div>
+        )}
+
+        {/* Power Adapter */}
+        {battery.adapter && (
+          <div className="card">
+            <h2 className="text-sm font-medium text-white/60 mb-3">
+              Power Adapter
+            </h2>
+            {battery.adapter.is_underpowered && (
+              <div className="text-sm text-health-warning mb-3">
+                This adapter can't keep up with your Mac. Use a higher wattage
+                adapter to charge.
+              </div>
+            )}
+            <div className="space-y-2">
+              {battery.adapter.name && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">Name</span>
+                  <span>{battery.adapter.name}</span>
+                </div>
+              )}
+              {battery.adapter.watts !== null && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">Wattage</span>
+                  <span>{battery.adapter.watts} W</span>
+                </div>
+              )}
+              {battery.adapter.voltage_volts !== null &&
+                battery.adapter.current_amps !== null && (
+                  <div className="flex justify-between text-sm">
+                    <span className="text-white/60">Negotiated</span>
+                    <span>
+                      {battery.adapter.voltage_volts.toFixed(1)} V /{" "}
+                      {battery.adapter.current_amps.toFixed(2)} A
+                    </span>
+                  </div>
+                )}
+              {battery.adapter.description && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">Type</span>
+                  <span>{battery.adapter.description}</span>
+                </div>
+              )}
+            </div>
+          </div>
+        )}
+
+        {/* Power */}
+        {(battery.power_draw_watts !== null ||
+          battery.amperage_ma !== null ||
+          battery.cell_voltages_mv.length > 0) && (
+          <div className="card">
+            <h2 className="text-sm font-medium text-white/60 mb-3">Power</h2>
+            <div className="space-y-2">
+              {battery.power_draw_watts !== null && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">Power Draw</span>
+                  <span>{battery.power_draw_watts.toFixed(1)} W</span>
+                </div>
+              )}
+              {battery.amperage_ma !== null && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">Amperage</span>
+                  <span>{Math.round(battery.amperage_ma)} mA</span>
+                </div>
+              )}
+              {battery.cell_voltages_mv.map((millivolts, index) => (
+                <div key={index} className="flex justify-between text-sm">
+                  <span className="text-white/60">Cell {index + 1}</span>
+                  <span>{(millivolts / 1000).toFixed(3)} V</span>
+                </div>
+              ))}
+              {battery.cell_imbalance_mv !== null && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">Cell Imbalance</span>
+                  <span>{battery.cell_imbalance_mv} mV</span>
+                </div>
+              )}
+            </div>
+          </div>
+        )}
+
+        {/* Sleep Blockers */}
+        {assertions.length > 0 && (
+          <div className="card">
+            <h2 className="text-sm font-medium text-white/60 mb-3">
+              Keeping Your Mac Awake
+            </h2>
+            {quitResult && (
+              <div className="text-sm text-white/60 mb-3">{quitResult}</div>
+            )}
+            <div className="space-y-2">
+              {assertions.map((assertion) => (
+                <div
+                  key={`${assertion.pid}-${assertion.assertion_type}-${assertion.name}`}
+                  className="flex items-center justify-between text-sm"
+                  title={assertion.name ?? assertion.assertion_type}
+                >
+                  <div className="truncate flex-1 mr-2">
+                    <div className="truncate">{assertion.process_name}</div>
+                    <div className="text-[10px] text-white/40">
+                      {assertion.prevents_display_sleep
+                        ? "Prevents display sleep"
+                        : "Prevents sleep"}
+                      {assertion.held_seconds !== null &&
+                        ` for ${formatTimeRemaining(Math.round(assertion.held_seconds / 60))}`}
+                    </div>
+                  </div>
+                  <button
+                    onClick={() =>
+                      handleForceQuit(assertion.pid, assertion.process_name)
+                    }
+                    className="p-1 hover:bg-red-500/20 rounded"
+                    title="Force Quit"
+                  >
+                    <svg
+                      width="16"
+                      height="16"
+                      viewBox="0 0 24 24"
+                      fill="none"
+                      stroke="currentColor"
+                      strokeWidth="2"
+                      strokeLinecap="round"
+                      strokeLinejoin="round"
+                      className="text-red-400"
+                    >
+                      <path d="M18 6 6 18" />
+                      <path d="m6 6 12 12" />
+                    </svg>
+                  </button>
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
+
         {/* Action Button */}
         <button
           onClick={handleOpenEnergySettings}
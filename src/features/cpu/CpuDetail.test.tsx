@@ -1,7 +1,17 @@
-import { render, screen, fireEvent, waitFor } from "@testing-library/react";
+import {
+  render,
+  screen,
+  fireEvent,
+  waitFor,
+  act,
+} from "@testing-library/react";
 import { describe, it, expect, vi, beforeEach } from "vitest";
 import { CpuDetail } from "./CpuDetail";
 
+const events = vi.hoisted(() => ({
+  cpu: null as ((sample: unknown) => void) | null,
+}));
+
 // Mock Tauri APIs
 vi.mock("../../lib/tauri", () => ({
   getTopCpuProcesses: vi.fn().mockResolvedValue([
@@ -9,9 +19,49 @@ vi.mock("../../lib/tauri", () => ({
     { pid: 2, name: "Chrome", cpu_percentage: 15.2 },
   ]),
   getSystemUptime: vi.fn().mockResolvedValue(86400), // 1 day
+  getCpuHistory: vi.fn().mockResolvedValue({
+    slot_ms: 3000,
+    points: [
+      {
+        timestamp_ms: 0,
+        sample_count: 1,
+        total_usage_percentage: 20,
+        per_core_usage: [20, 20],
+        load_average: {
+          one_minute: 1.5,
+          five_minutes: 1.2,
+          fifteen_minutes: 1,
+        },
+      },
+      {
+        timestamp_ms: 3000,
+        sample_count: 0,
+        total_usage_percentage: null,
+        per_core_usage: [],
+        load_average: null,
+      },
+      {
+        timestamp_ms: 6000,
+        sample_count: 1,
+        total_usage_percentage: 30,
+        per_core_usage: [25, 35],
+        load_average: {
+          one_minute: 2.25,
+          five_minutes: 1.3,
+          fifteen_minutes: 1,
+        },
+      },
+    ],
+  }),
+  onMetricSample: vi.fn((metric: string, handler: (s: unknown) => void) => {
+    if (metric === "cpu") events.cpu = handler;
+    return Promise.resolve(() => {});
+  }),
   openActivityMonitor: vi.fn().mockResolvedValue(undefined),
 }));
 
+const storeState = vi.hoisted(() => ({ isEstimate: false }));
+
 // Mock Zustand store
 vi.mock("../../store/systemStore", () => ({
   useSystemStore: vi.fn((selector) => {
@@ -19,13 +69,84 @@ vi.mock("../../store/systemStore", () => ({
       cpu: {
         model_name: "Apple M3",
         total_cores: 8,
+        physical_cores: 8,
+        performance_cores: 4,
+        efficiency_cores: 4,
+        clusters: [
+          {
+            name: "Performance",
+            kind: "performance",
+            physical_cores: 4,
+            logical_cores: 4,
+            cores_per_l2: 4,
+            cache: {
+              l1_instruction_bytes: 196608,
+              l1_data_bytes: 131072,
+              l2_bytes: 16777216,
+              l3_bytes: null,
+            },
+          },
+          {
+            name: "Efficiency",
+            kind: "efficiency",
+            physical_cores: 4,
+            logical_cores: 4,
+            cores_per_l2: 4,
+            cache: {
+              l1_instruction_bytes: 131072,
+              l1_data_bytes: 65536,
+              l2_bytes: 4194304,
+              l3_bytes: null,
+            },
+          },
+        ],
+        cache: {
+          l1_instruction_bytes: 131072,
+          l1_data_bytes: 65536,
+          l2_bytes: 4194304,
+          l3_bytes: null,
+        },
         total_usage_percentage: 25.0,
-        per_core_usage: [20, 30, 25, 22, 15, 18, 20, 25],
+        is_estimate: storeState.isEstimate,
+        breakdown: storeState.isEstimate
+          ? null
+          : {
+              user_percentage: 15.5,
+              system_percentage: 9.5,
+              idle_percentage: 75.0,
+              nice_percentage: null,
+              iowait_percentage: null,
+              steal_percentage: null,
+            },
+        per_core_usage: [
+          { usage_percentage: 60, kind: "efficiency", frequency_mhz: 2064 },
+          { usage_percentage: 70, kind: "efficiency", frequency_mhz: 2064 },
+          { usage_percentage: 65, kind: "efficiency", frequency_mhz: 2064 },
+          { usage_percentage: 62, kind: "efficiency", frequency_mhz: 2064 },
+          { usage_percentage: 15, kind: "performance", frequency_mhz: 3228 },
+          { usage_percentage: 18, kind: "performance", frequency_mhz: 3228 },
+          { usage_percentage: 20, kind: "performance", frequency_mhz: 3228 },
+          { usage_percentage: 25, kind: "performance", frequency_mhz: 3228 },
+        ],
         load_average: {
           one_minute: 1.5,
           five_minutes: 1.2,
           fifteen_minutes: 1.0,
         },
+        frequency: {
+          current_mhz: 2646,
+          nominal_mhz: null,
+          max_mhz: 4056,
+        },
+        thermal: {
+          pressure: "moderate",
+          is_throttled: true,
+          cpu_speed_limit_percentage: 78,
+          scheduler_limit_percentage: 100,
+          available_cpus: 8,
+          thermal_warning_level: null,
+          performance_warning_level: null,
+        },
       },
     };
     return selector(state);
@@ -35,6 +156,7 @@ vi.mock("../../store/systemStore", () => ({
 describe("CpuDetail", () => {
   beforeEach(() => {
     vi.clearAllMocks();
+    storeState.isEstimate = false;
   });
 
   it("renders the header", () => {
@@ -62,6 +184,28 @@ describe("CpuDetail", () => {
     expect(screen.getByText("Per-Core Usage")).toBeInTheDocument();
   });
 
+  it("renders performance and efficiency cores", () => {
+    render(<CpuDetail />);
+    expect(
+      screen.getByText("4 performance · 4 efficiency")
+    ).toBeInTheDocument();
+    expect(screen.getByText("E1")).toBeInTheDocument();
+    expect(screen.getByText("P5")).toBeInTheDocument();
+    expect(screen.getByText("Efficiency Cores")).toBeInTheDocument();
+    expect(screen.getByText("64%")).toBeInTheDocument();
+    expect(screen.getByText("Performance Cores")).toBeInTheDocument();
+    expect(screen.getByText("20%")).toBeInTheDocument();
+  });
+
+  it("renders clock speeds that are known", () => {
+    render(<CpuDetail />);
+    expect(screen.getByText("Clock Speed")).toBeInTheDocument();
+    expect(screen.getByText("2.65 GHz")).toBeInTheDocument();
+    expect(screen.getByText("Max Clock")).toBeInTheDocument();
+    expect(screen.getByText("4.06 GHz")).toBeInTheDocument();
+    expect(screen.queryByText("Base Clock")).not.toBeInTheDocument();
+  });
+
   it("renders system info section", () => {
     render(<CpuDetail />);
     expect(screen.getByText("System Info")).toBeInTheDocument();
@@ -72,6 +216,76 @@ describe("CpuDetail", () => {
     expect(screen.getByText("1.20")).toBeInTheDocument();
   });
 
+  it("renders the usage breakdown", () => {
+    render(<CpuDetail />);
+    expect(screen.getByText("User")).toBeInTheDocument();
+    expect(screen.getByText("15.5%")).toBeInTheDocument();
+    expect(screen.getByText("System")).toBeInTheDocument();
+    expect(screen.getByText("9.5%")).toBeInTheDocument();
+    expect(screen.getByText("Idle")).toBeInTheDocument();
+    expect(screen.getByText("75.0%")).toBeInTheDocument();
+    // Only the states the platform reports
+    expect(screen.queryByText("Nice")).not.toBeInTheDocument();
+    expect(screen.queryByText("I/O Wait")).not.toBeInTheDocument();
+    expect(screen.queryByText(/Estimated/)).not.toBeInTheDocument();
+  });
+
+  it("marks usage estimated from the load average", () => {
+    storeState.isEstimate = true;
+    render(<CpuDetail />);
+    expect(screen.getByText("Estimated from load average")).toBeInTheDocument();
+    expect(screen.queryByText("User")).not.toBeInTheDocument();
+  });
+
+  it("renders thermal state", () => {
+    render(<CpuDetail />);
+    expect(screen.getByText("Thermal")).toBeInTheDocument();
+    expect(screen.getByText("moderate")).toBeInTheDocument();
+    expect(screen.getByText("78%")).toBeInTheDocument();
+    expect(
+      screen.getByText("The CPU is being slowed down to keep it cool.")
+    ).toBeInTheDocument();
+  });
+
+  it("renders recent activity sparklines", async () => {
+    render(<CpuDetail />);
+    await waitFor(() => {
+      expect(screen.getByText("Recent Activity")).toBeInTheDocument();
+    });
+    expect(
+      screen.getByRole("img", { name: "CPU usage history" })
+    ).toBeInTheDocument();
+    expect(
+      screen.getByRole("img", { name: "Load average history" })
+    ).toBeInTheDocument();
+    expect(screen.getByText("2.25")).toBeInTheDocument();
+  });
+
+  it("adds CPU samples to the recent activity as they arrive", async () => {
+    render(<CpuDetail />);
+    await waitFor(() => {
+      expect(screen.getByText("2.25")).toBeInTheDocument();
+    });
+
+    act(() => {
+      events.cpu?.({
+        timestamp_ms: 9400,
+        data: {
+          total_usage_percentage: 40,
+          is_estimate: false,
+          per_core_usage: [],
+          load_average: {
+            one_minute: 3.5,
+            five_minutes: 1.4,
+            fifteen_minutes: 1.1,
+          },
+        },
+        error: null,
+      });
+    });
+    expect(screen.getByText("3.50")).toBeInTheDocument();
+  });
+
   it("renders top CPU consumers", async () => {
     render(<CpuDetail />);
     await waitFor(() => {
@@ -1,25 +1,98 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { BackButton } from "../../components/ui";
-import { GaugeChart, BarChart } from "../../components/charts";
+import { GaugeChart, BarChart, Sparkline } from "../../components/charts";
 import { useSystemStore } from "../../store/systemStore";
-import { formatUptime } from "../../lib/formatters";
 import {
+  formatFrequency,
+  formatPercent,
+  formatUptime,
+} from "../../lib/formatters";
+import {
+  getCpuHistory,
   getTopCpuProcesses,
   getSystemUptime,
+  onMetricSample,
   openActivityMonitor,
 } from "../../lib/tauri";
-import type { ProcessCpuInfo } from "../../types";
+import type { CpuHistoryPoint, CpuInfo, ProcessCpuInfo } from "../../types";
+
+// Until the backend says otherwise, the default CPU sampling interval
+const DEFAULT_SLOT_MS = 3000;
+const HISTORY_WINDOW_MS = 10 * 60 * 1000;
+
+/** Add a live CPU sample to the history, bucketed like the backend does */
+function appendSample(
+  history: CpuHistoryPoint[],
+  slotMs: number,
+  timestampMs: number,
+  cpu: CpuInfo
+): CpuHistoryPoint[] {
+  const slot = timestampMs - (timestampMs % slotMs);
+  const last = history[history.length - 1];
+  // Already part of the fetched history
+  if (last && last.timestamp_ms >= slot) return history;
+
+  const point: CpuHistoryPoint = {
+    timestamp_ms: slot,
+    sample_count: 1,
+    total_usage_percentage: cpu.is_estimate ? null : cpu.total_usage_percentage,
+    per_core_usage: cpu.per_core_usage.map((core) => core.usage_percentage),
+    load_average: cpu.load_average,
+  };
+  return [
+    ...history.filter((p) => p.timestamp_ms > slot - HISTORY_WINDOW_MS),
+    point,
+  ];
+}
 
 export function CpuDetail() {
   const cpu = useSystemStore((s) => s.cpu);
   const [processes, setProcesses] = useState<ProcessCpuInfo[]>([]);
   const [uptime, setUptime] = useState<number>(0);
+  const [history, setHistory] = useState<CpuHistoryPoint[]>([]);
+  const slotMs = useRef(DEFAULT_SLOT_MS);
 
   useEffect(() => {
     getTopCpuProcesses(8).then(setProcesses).catch(console.error);
     getSystemUptime().then(setUptime).catch(console.error);
   }, []);
 
+  // Fetch what the backend kept once, then follow the sampler's events
+  useEffect(() => {
+    getCpuHistory("sample")
+      .then(({ slot_ms: fetchedSlotMs, points }) => {
+        slotMs.current = fetchedSlotMs;
+        setHistory((live) => {
+          const lastFetched = points[points.length - 1]?.timestamp_ms ?? -1;
+          return [
+            ...points,
+            ...live.filter((point) => point.timestamp_ms > lastFetched),
+          ];
+        });
+      })
+      .catch(console.error);
+
+    const unlisten = onMetricSample("cpu", (sample) => {
+      const data = sample.data;
+      if (data) {
+        setHistory((points) =>
+          appendSample(points, slotMs.current, sample.timestamp_ms, data)
+        );
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Empty slots would break the line up between every sample
+  const sampled = history.filter((point) => point.sample_count > 0);
+  const usageHistory = sampled.map((point) => point.total_usage_percentage);
+  const loadHistory = sampled.map(
+    (point) => point.load_average?.one_minute ?? null
+  );
+  const latestLoad = loadHistory[loadHistory.length - 1] ?? null;
+
   const percentage = cpu?.total_usage_percentage ?? 0;
   const modelName = cpu?.model_name ?? "Unknown";
 
@@ -31,8 +104,46 @@ export function CpuDetail() {
     }
   };
 
-  // Generate labels for cores
-  const coreLabels = cpu?.per_core_usage?.map((_, i) => String(i + 1)) ?? [];
+  // Only the states the platform reports
+  const breakdown = cpu?.breakdown ?? null;
+  const breakdownRows = breakdown
+    ? [
+        { label: "User", value: breakdown.user_percentage },
+        { label: "System", value: breakdown.system_percentage },
+        { label: "Nice", value: breakdown.nice_percentage },
+        { label: "I/O Wait", value: breakdown.iowait_percentage },
+        { label: "Steal", value: breakdown.steal_percentage },
+        { label: "Idle", value: breakdown.idle_percentage },
+      ].filter((row) => row.value !== null)
+    : [];
+
+  const thermal = cpu?.thermal ?? null;
+  const thermalColor = thermal
+    ? {
+        nominal: "text-health-excellent",
+        moderate: "text-health-warning",
+        heavy: "text-health-critical",
+        critical: "text-health-critical",
+      }[thermal.pressure]
+    : "";
+
+  // Generate labels for cores, prefixed P or E when the cluster is known
+  const perCoreUsage = cpu?.per_core_usage ?? [];
+  const coreValues = perCoreUsage.map((core) => core.usage_percentage);
+  const kindPrefix = { performance: "P", efficiency: "E" };
+  const coreLabels = perCoreUsage.map(
+    (core, i) => `${core.kind ? kindPrefix[core.kind] : ""}${i + 1}`
+  );
+
+  // Average usage per cluster, to show where the heavy work landed
+  const clusterUsage = (cpu?.clusters ?? []).map((cluster) => {
+    const cores = perCoreUsage.filter((core) => core.kind === cluster.kind);
+    const total = cores.reduce((sum, core) => sum + core.usage_percentage, 0);
+    return {
+      name: cluster.name,
+      percentage: cores.length > 0 ? total / cores.length : null,
+    };
+  });
 
   return (
     <div className="flex flex-col h-full">
@@ -58,22 +169,134 @@ export function CpuDetail() {
             <div className="text-xs text-white/40">
               {cpu?.total_cores ?? "--"} cores
             </div>
+            {cpu?.is_estimate && (
+              <div className="text-xs text-health-warning">
+                Estimated from load average
+              </div>
+            )}
+            {cpu &&
+              cpu.performance_cores !== null &&
+              cpu.efficiency_cores !== null && (
+                <div className="text-xs text-white/40">
+                  {cpu.performance_cores} performance ·{" "}
+                  {cpu.efficiency_cores} efficiency
+                </div>
+              )}
           </div>
         </div>
 
+        {/* Recent Activity */}
+        {sampled.length > 1 && (
+          <div className="card">
+            <h2 className="text-sm font-medium text-white/60 mb-3">
+              Recent Activity
+            </h2>
+            <div className="space-y-3">
+              <div>
+                <div className="flex justify-between text-xs mb-1">
+                  <span className="text-white/60">Usage</span>
+                  <span className="text-white/40">Last 10 minutes</span>
+                </div>
+                <Sparkline
+                  values={usageHistory}
+                  maxValue={100}
+                  label="CPU usage history"
+                />
+              </div>
+              <div>
+                <div className="flex justify-between text-xs mb-1">
+                  <span className="text-white/60">Load</span>
+                  <span>{latestLoad?.toFixed(2) ?? "--"}</span>
+                </div>
+                <Sparkline
+                  values={loadHistory}
+                  color="#ec4899"
+                  label="Load average history"
+                />
+              </div>
+            </div>
+          </div>
+        )}
+
+        {/* Usage Breakdown */}
+        {breakdownRows.length > 0 && (
+          <div className="card">
+            <h2 className="text-sm font-medium text-white/60 mb-3">
+              Usage Breakdown
+            </h2>
+            <div className="space-y-2">
+              {breakdownRows.map((row) => (
+                <div key={row.label} className="flex justify-between text-sm">
+                  <span className="text-white/60">{row.label}</span>
+                  <span>{formatPercent(row.value ?? 0, 1)}</span>
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
+
         {/* Per-Core Usage */}
-        {cpu?.per_core_usage && cpu.per_core_usage.length > 0 && (
+        {coreValues.length > 0 && (
           <div className="card">
             <h2 className="text-sm font-medium text-white/60 mb-3">
               Per-Core Usage
             </h2>
             <BarChart
-              values={cpu.per_core_usage}
+              values={coreValues}
               maxValue={100}
               height={60}
               gradientColors={["#ec4899", "#8b5cf6"]}
               labels={coreLabels}
             />
+            {clusterUsage.some((cluster) => cluster.percentage !== null) && (
+              <div className="mt-3 space-y-1">
+                {clusterUsage.map((cluster) => (
+                  <div
+                    key={cluster.name}
+                    className="flex justify-between text-sm"
+                  >
+                    <span className="text-white/60">{cluster.name} Cores</span>
+                    <span>
+                      {cluster.percentage !== null
+                        ? `${cluster.percentage.toFixed(0)}%`
+                        : "--"}
+                    </span>
+                  </div>
+                ))}
+              </div>
+            )}
+          </div>
+        )}
+
+        {/* Thermal State */}
+        {thermal && (
+          <div className="card">
+            <h2 className="text-sm font-medium text-white/60 mb-3">Thermal</h2>
+            <div className="space-y-2">
+              <div className="flex justify-between text-sm">
+                <span className="text-white/60">Pressure</span>
+                <span className={`capitalize ${thermalColor}`}>
+                  {thermal.pressure}
+                </span>
+              </div>
+              {thermal.cpu_speed_limit_percentage !== null && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">CPU Speed Limit</span>
+                  <span>{thermal.cpu_speed_limit_percentage}%</span>
+                </div>
+              )}
+              {thermal.scheduler_limit_percentage !== null && (
+                <div className="flex justify-between text-sm">
+                  <span className="text-white/60">Scheduler Limit</span>
+                  <span>{thermal.scheduler_limit_percentage}%</span>
+                </div>
+              )}
+              {thermal.is_throttled && (
+                <div className="text-xs text-white/40">
+                  The CPU is being slowed down to keep it cool.
+                </div>
+              )}
+            </div>
           </div>
         )}
 
@@ -87,8 +310,26 @@ export function CpuDetail() {
               <span className="text-white/60">Uptime</span>
               <span>{formatUptime(uptime)}</span>
             </div>
+            {cpu?.frequency.current_mhz != null && (
+              <div className="flex justify-between text-sm">
+                <span className="text-white/60">Clock Speed</span>
+                <span>{formatFrequency(cpu.frequency.current_mhz)}</span>
+              </div>
+            )}
+            {cpu?.frequency.nominal_mhz != null && (
+              <div className="flex justify-between text-sm">
+                <span className="text-white/60">Base Clock</span>
+                <span>{formatFrequency(cpu.frequency.nominal_mhz)}</span>
+              </div>
+            )}
+            {cpu?.frequency.max_mhz != null && (
+              <div className="flex justify-between text-sm">
+                <span className="text-white/60">Max Clock</span>
+                <span>{formatFrequency(cpu.frequency.max_mhz)}</span>
+              </div>
+            )}
             <div className="flex justify-between text-sm">
-              <span className="text-white/60">Load Average (1m)</span>
+              <span className="text-white/60">Load</span>
               <span>{cpu?.load_average.one_minute.toFixed(2) ?? "--"}</span>
             </div>
             <div className="flex justify-between text-sm">
@@ -9,6 +9,9 @@ interface CpuWidgetProps {
 export function CpuWidget({ data, onClick }: CpuWidgetProps) {
   const percentage = data?.total_usage_percentage ?? 0;
   const modelName = data?.model_name ?? "Unknown";
+  // Marked when it is a guess from the load average, not a measurement
+  const estimated = data?.is_estimate ?? false;
+  const usageLabel = `${estimated ? "~" : ""}${Math.round(percentage)}%`;
 
   // Shorten model name for display
   const shortModelName = modelName
@@ -20,7 +23,7 @@ export function CpuWidget({ data, onClick }: CpuWidgetProps) {
     <button
       className="widget-card flex flex-col items-center gap-3"
       onClick={onClick}
-      aria-label={`CPU: ${Math.round(percentage)}% usage. ${modelName}. Click for details.`}
+      aria-label={`CPU: ${Math.round(percentage)}%${estimated ? " estimated" : ""} usage. ${modelName}. Click for details.`}
     >
       <div className="text-sm font-medium text-white/60">CPU</div>
       <CircularProgress
@@ -30,7 +33,7 @@ export function CpuWidget({ data, onClick }: CpuWidgetProps) {
         gradientId="cpu-gradient"
         gradientColors={["#ec4899", "#8b5cf6"]}
       >
-        <span className="text-lg font-semibold">{Math.round(percentage)}%</span>
+        <span className="text-lg font-semibold">{usageLabel}</span>
       </CircularProgress>
       <div className="text-center">
         <div className="text-sm font-medium">{shortModelName}</div>
@@ -0,0 +1,25 @@
+import { useEffect } from "react";
+import { onMetricSample } from "../lib/tauri";
+import { useSystemStore } from "../store/systemStore";
+import type { MetricKind } from "../types";
+
+const METRICS: MetricKind[] = ["ram", "cpu", "disk", "battery"];
+
+/**
+ * Feed the store from the metric events pushed by the backend sampler.
+ */
+export function useMetricEvents(enabled: boolean = true) {
+  const receiveSample = useSystemStore((s) => s.receiveSample);
+
+  useEffect(() => {
+    if (!enabled) return;
+
+    const unlisteners = METRICS.map((metric) =>
+      onMetricSample(metric, (sample) => receiveSample(metric, sample))
+    );
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+    };
+  }, [receiveSample, enabled]);
+}
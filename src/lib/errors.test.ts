@@ -0,0 +1,33 @@
+import { describe, it, expect } from "vitest";
+import { isMonitorError, errorMessage } from "./errors";
+
+const noBattery = {
+  code: "NoBattery",
+  message: "No battery found (desktop Mac?)",
+};
+
+describe("isMonitorError", () => {
+  it("recognizes backend errors", () => {
+    expect(isMonitorError(noBattery)).toBe(true);
+    expect(isMonitorError("No battery found")).toBe(false);
+    expect(isMonitorError(null)).toBe(false);
+  });
+
+  it("matches by code", () => {
+    expect(isMonitorError(noBattery, "NoBattery", "Unsupported")).toBe(true);
+    expect(isMonitorError(noBattery, "CommandFailed")).toBe(false);
+  });
+});
+
+describe("errorMessage", () => {
+  it("prefers the backend message", () => {
+    expect(errorMessage(noBattery, "fallback")).toBe(
+      "No battery found (desktop Mac?)"
+    );
+  });
+
+  it("falls back for unknown values", () => {
+    expect(errorMessage(new Error("boom"), "fallback")).toBe("boom");
+    expect(errorMessage(42, "fallback")).toBe("fallback");
+  });
+});
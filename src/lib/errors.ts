@@ -0,0 +1,25 @@
+import type { MonitorError, MonitorErrorCode } from "../types";
+
+/**
+ * Check whether a rejected command returned a backend MonitorError,
+ * optionally with one of the given codes.
+ */
+export function isMonitorError(
+  error: unknown,
+  ...codes: MonitorErrorCode[]
+): error is MonitorError {
+  if (typeof error !== "object" || error === null || !("code" in error)) {
+    return false;
+  }
+  const { code } = error as MonitorError;
+  return codes.length === 0 || codes.includes(code);
+}
+
+/**
+ * Human-readable message for a rejected command
+ */
+export function errorMessage(error: unknown, fallback: string): string {
+  if (isMonitorError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return fallback;
+}
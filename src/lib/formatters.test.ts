@@ -5,6 +5,7 @@ import {
   formatPercent,
   formatUptime,
   formatTimeRemaining,
+  formatFrequency,
 } from "./formatters";
 
 describe("formatBytes", () => {
@@ -72,6 +73,20 @@ describe("formatPercent", () => {
   });
 });
 
+describe("formatFrequency", () => {
+  it("returns -- for null", () => {
+    expect(formatFrequency(null)).toBe("--");
+  });
+
+  it("formats MHz below 1 GHz", () => {
+    expect(formatFrequency(800)).toBe("800 MHz");
+  });
+
+  it("formats GHz from 1000 MHz", () => {
+    expect(formatFrequency(3228)).toBe("3.23 GHz");
+  });
+});
+
 describe("formatUptime", () => {
   it("formats seconds to less than 1 minute", () => {
     expect(formatUptime(30)).toBe("< 1m");
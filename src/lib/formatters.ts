@@ -43,6 +43,15 @@ export function formatUptime(seconds: number): string {
   return parts.length > 0 ? parts.join(" ") : "< 1m";
 }
 
+/**
+ * Format a clock speed in MHz, as GHz from 1000 MHz up
+ */
+export function formatFrequency(mhz: number | null): string {
+  if (mhz === null) return "--";
+  if (mhz < 1000) return `${mhz} MHz`;
+  return `${(mhz / 1000).toFixed(2)} GHz`;
+}
+
 /**
  * Format time remaining in minutes
  */
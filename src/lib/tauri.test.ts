@@ -1,5 +1,6 @@
 import { describe, it, expect, vi, beforeEach } from "vitest";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import {
   getRamInfo,
   getTopMemoryProcesses,
@@ -10,12 +11,28 @@ import {
   getSystemUptime,
   openActivityMonitor,
   getBatteryInfo,
+  getBatteryDetails,
+  getPowerSource,
+  getSleepTimeline,
+  getSleepAssertions,
+  setLowPowerModeWithAdmin,
   openEnergySettings,
   getDiskInfo,
   getStorageCategories,
   refreshStorageCategories,
   openStorageSettings,
   openSystemSettings,
+  getSystemSnapshot,
+  setSamplingInterval,
+  onMetricSample,
+  getCpuHistory,
+  getBatteryHistory,
+  getBatterySessions,
+  getBatteryHealthForecast,
+  startKeepAwake,
+  stopKeepAwake,
+  getKeepAwakeStatus,
+  onKeepAwakeChange,
   updateTrayStatus,
 } from "./tauri";
 
@@ -24,6 +41,10 @@ vi.mock("@tauri-apps/api/core", () => ({
   invoke: vi.fn(),
 }));
 
+vi.mock("@tauri-apps/api/event", () => ({
+  listen: vi.fn(),
+}));
+
 describe("tauri lib", () => {
   beforeEach(() => {
     vi.clearAllMocks();
@@ -93,6 +114,52 @@ describe("tauri lib", () => {
       expect(invoke).toHaveBeenCalledWith("get_battery_info");
     });
 
+    it("getBatteryDetails calls invoke", async () => {
+      vi.mocked(invoke).mockResolvedValue({ serial: "F8Y2147A1UPQ1CWAH" });
+      const result = await getBatteryDetails();
+      expect(result.serial).toBe("F8Y2147A1UPQ1CWAH");
+      expect(invoke).toHaveBeenCalledWith("get_battery_details");
+    });
+
+    it("getPowerSource calls invoke", async () => {
+      vi.mocked(invoke).mockResolvedValue({
+        source: "ups",
+        has_internal_battery: false,
+        ups: null,
+      });
+      const result = await getPowerSource();
+      expect(result.source).toBe("ups");
+      expect(invoke).toHaveBeenCalledWith("get_power_source");
+    });
+
+    it("getSleepTimeline passes the time range", async () => {
+      const timeline = { events: [], periods: [] };
+      vi.mocked(invoke).mockResolvedValue(timeline);
+      const result = await getSleepTimeline(1000, 2000);
+      expect(result).toEqual(timeline);
+      expect(invoke).toHaveBeenCalledWith("get_sleep_timeline", {
+        fromMs: 1000,
+        toMs: 2000,
+      });
+    });
+
+    it("getSleepAssertions calls invoke", async () => {
+      vi.mocked(invoke).mockResolvedValue([]);
+      const result = await getSleepAssertions();
+      expect(result).toEqual([]);
+      expect(invoke).toHaveBeenCalledWith("get_sleep_assertions");
+    });
+
+    it("setLowPowerModeWithAdmin passes the source and state", async () => {
+      vi.mocked(invoke).mockResolvedValue({ success: true, message: "" });
+      const result = await setLowPowerModeWithAdmin("battery", true);
+      expect(result.success).toBe(true);
+      expect(invoke).toHaveBeenCalledWith("set_low_power_mode_with_admin", {
+        source: "battery",
+        enabled: true,
+      });
+    });
+
     it("openEnergySettings calls invoke", async () => {
       vi.mocked(invoke).mockResolvedValue(undefined);
       await openEnergySettings();
@@ -134,6 +201,166 @@ describe("tauri lib", () => {
     });
   });
 
+  describe("Snapshot commands", () => {
+    it("getSystemSnapshot calls invoke with process count", async () => {
+      vi.mocked(invoke).mockResolvedValue({ sequence: 1 });
+      const result = await getSystemSnapshot(3);
+      expect(invoke).toHaveBeenCalledWith("get_system_snapshot", {
+        processCount: 3,
+      });
+      expect(result).toEqual({ sequence: 1 });
+    });
+  });
+
+  describe("Sampling", () => {
+    it("setSamplingInterval calls invoke with metric and interval", async () => {
+      vi.mocked(invoke).mockResolvedValue(undefined);
+      await setSamplingInterval("disk", 60000);
+      expect(invoke).toHaveBeenCalledWith("set_sampling_interval", {
+        metric: "disk",
+        intervalMs: 60000,
+      });
+    });
+
+    it("onMetricSample listens to the metric event", async () => {
+      const unlisten = vi.fn();
+      vi.mocked(listen).mockResolvedValue(unlisten);
+      const handler = vi.fn();
+
+      const result = await onMetricSample("cpu", handler);
+      expect(result).toBe(unlisten);
+      expect(listen).toHaveBeenCalledWith(
+        "metrics://cpu",
+        expect.any(Function)
+      );
+
+      const callback = vi.mocked(listen).mock.calls[0][1];
+      const sample = { timestamp_ms: 1, data: null, error: null };
+      callback({ event: "metrics://cpu", id: 1, payload: sample });
+      expect(handler).toHaveBeenCalledWith(sample);
+    });
+  });
+
+  describe("History commands", () => {
+    it("getCpuHistory passes the resolution", async () => {
+      const series = {
+        slot_ms: 60000,
+        points: [
+          {
+            timestamp_ms: 60000,
+            sample_count: 20,
+            total_usage_percentage: 12.5,
+            per_core_usage: [10, 15],
+            load_average: {
+              one_minute: 1.5,
+              five_minutes: 1.2,
+              fifteen_minutes: 1.0,
+            },
+          },
+        ],
+      };
+      vi.mocked(invoke).mockResolvedValue(series);
+      const result = await getCpuHistory("minute");
+      expect(invoke).toHaveBeenCalledWith("get_cpu_history", {
+        resolution: "minute",
+      });
+      expect(result).toEqual(series);
+    });
+
+    it("getBatteryHistory passes the time range", async () => {
+      vi.mocked(invoke).mockResolvedValue([]);
+      await getBatteryHistory(1000, 2000);
+      expect(invoke).toHaveBeenCalledWith("get_battery_history", {
+        fromMs: 1000,
+        toMs: 2000,
+      });
+    });
+
+    it("getBatterySessions returns sessions", async () => {
+      const sessions = [
+        {
+          kind: "discharging",
+          start_ms: 0,
+          end_ms: 3600000,
+          duration_minutes: 60,
+          start_percentage: 100,
+          end_percentage: 82,
+          average_watts: 9.5,
+        },
+      ];
+      vi.mocked(invoke).mockResolvedValue(sessions);
+
+      const result = await getBatterySessions();
+      expect(result).toEqual(sessions);
+      expect(invoke).toHaveBeenCalledWith("get_battery_sessions", {
+        fromMs: undefined,
+        toMs: undefined,
+      });
+    });
+
+    it("getBatteryHealthForecast calls invoke", async () => {
+      const forecast = {
+        samples: [],
+        loss_per_month_percentage: null,
+        loss_per_100_cycles_percentage: null,
+        service_recommended_at_ms: null,
+        replace_soon_at_ms: null,
+      };
+      vi.mocked(invoke).mockResolvedValue(forecast);
+
+      const result = await getBatteryHealthForecast();
+      expect(result).toEqual(forecast);
+      expect(invoke).toHaveBeenCalledWith("get_battery_health_forecast");
+    });
+  });
+
+  describe("Keep-awake commands", () => {
+    const status = {
+      active: true,
+      mode: "display",
+      started_at_ms: 1000,
+      ends_at_ms: 1801000,
+    };
+
+    it("startKeepAwake passes the mode and duration", async () => {
+      vi.mocked(invoke).mockResolvedValue(status);
+      const result = await startKeepAwake("display", 30);
+      expect(result).toEqual(status);
+      expect(invoke).toHaveBeenCalledWith("start_keep_awake", {
+        mode: "display",
+        durationMinutes: 30,
+      });
+    });
+
+    it("stopKeepAwake calls invoke", async () => {
+      vi.mocked(invoke).mockResolvedValue({ ...status, active: false });
+      await stopKeepAwake();
+      expect(invoke).toHaveBeenCalledWith("stop_keep_awake");
+    });
+
+    it("getKeepAwakeStatus calls invoke", async () => {
+      vi.mocked(invoke).mockResolvedValue(status);
+      const result = await getKeepAwakeStatus();
+      expect(result.active).toBe(true);
+      expect(invoke).toHaveBeenCalledWith("get_keep_awake_status");
+    });
+
+    it("onKeepAwakeChange listens to the change event", async () => {
+      vi.mocked(listen).mockResolvedValue(vi.fn());
+      const handler = vi.fn();
+
+      await onKeepAwakeChange(handler);
+      expect(listen).toHaveBeenCalledWith(
+        "keep-awake://changed",
+        expect.any(Function)
+      );
+
+      const callback = vi.mocked(listen).mock.calls[0][1];
+      callback({ event: "keep-awake://changed", id: 1, payload: status });
+      expect(handler).toHaveBeenCalledWith(status);
+    });
+  });
+
   describe("Tray commands", () => {
     it("updateTrayStatus calls invoke with status", async () => {
       vi.mocked(invoke).mockResolvedValue(undefined);
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
   RamInfo,
   ProcessMemoryInfo,
@@ -7,8 +8,25 @@ import type {
   CpuInfo,
   ProcessCpuInfo,
   BatteryInfo,
+  BatteryDetails,
+  PowerSourceInfo,
+  SettingSource,
+  LowPowerModeResult,
+  SleepTimeline,
+  SleepAssertion,
   DisksOverview,
   StorageCategories,
+  MetricKind,
+  MetricPayloads,
+  MetricSample,
+  SystemSnapshot,
+  CpuHistoryResolution,
+  CpuHistorySeries,
+  BatteryHistorySample,
+  BatterySession,
+  HealthForecast,
+  KeepAwakeMode,
+  KeepAwakeStatus,
 } from "../types";
 
 // RAM commands
@@ -54,6 +72,35 @@ export async function getBatteryInfo(): Promise<BatteryInfo> {
   return invoke<BatteryInfo>("get_battery_info");
 }
 
+export async function getBatteryDetails(): Promise<BatteryDetails> {
+  return invoke<BatteryDetails>("get_battery_details");
+}
+
+export async function getPowerSource(): Promise<PowerSourceInfo> {
+  return invoke<PowerSourceInfo>("get_power_source");
+}
+
+export async function getSleepTimeline(
+  fromMs?: number,
+  toMs?: number
+): Promise<SleepTimeline> {
+  return invoke<SleepTimeline>("get_sleep_timeline", { fromMs, toMs });
+}
+
+export async function getSleepAssertions(): Promise<SleepAssertion[]> {
+  return invoke<SleepAssertion[]>("get_sleep_assertions");
+}
+
+export async function setLowPowerModeWithAdmin(
+  source: SettingSource,
+  enabled: boolean
+): Promise<LowPowerModeResult> {
+  return invoke<LowPowerModeResult>("set_low_power_mode_with_admin", {
+    source,
+    enabled,
+  });
+}
+
 export async function openEnergySettings(): Promise<void> {
   return invoke<void>("open_energy_settings");
 }
@@ -81,6 +128,83 @@ export async function openSystemSettings(
   return invoke<void>("open_system_settings", { panel });
 }
 
+// Snapshot commands
+export async function getSystemSnapshot(
+  processCount?: number
+): Promise<SystemSnapshot> {
+  return invoke<SystemSnapshot>("get_system_snapshot", { processCount });
+}
+
+// Sampling
+export async function setSamplingInterval(
+  metric: MetricKind,
+  intervalMs: number
+): Promise<void> {
+  return invoke<void>("set_sampling_interval", { metric, intervalMs });
+}
+
+export async function onMetricSample<K extends MetricKind>(
+  metric: K,
+  handler: (sample: MetricSample<MetricPayloads[K]>) => void
+): Promise<UnlistenFn> {
+  return listen<MetricSample<MetricPayloads[K]>>(
+    `metrics://${metric}`,
+    (event) => handler(event.payload)
+  );
+}
+
+// History commands
+export async function getCpuHistory(
+  resolution?: CpuHistoryResolution
+): Promise<CpuHistorySeries> {
+  return invoke<CpuHistorySeries>("get_cpu_history", { resolution });
+}
+
+export async function getBatteryHistory(
+  fromMs?: number,
+  toMs?: number
+): Promise<BatteryHistorySample[]> {
+  return invoke<BatteryHistorySample[]>("get_battery_history", {
+    fromMs,
+    toMs,
+  });
+}
+
+export async function getBatterySessions(
+  fromMs?: number,
+  toMs?: number
+): Promise<BatterySession[]> {
+  return invoke<BatterySession[]>("get_battery_sessions", { fromMs, toMs });
+}
+
+export async function getBatteryHealthForecast(): Promise<HealthForecast> {
+  return invoke<HealthForecast>("get_battery_health_forecast");
+}
+
+// Keep-awake commands
+export async function startKeepAwake(
+  mode?: KeepAwakeMode,
+  durationMinutes?: number
+): Promise<KeepAwakeStatus> {
+  return invoke<KeepAwakeStatus>("start_keep_awake", { mode, durationMinutes });
+}
+
+export async function stopKeepAwake(): Promise<KeepAwakeStatus> {
+  return invoke<KeepAwakeStatus>("stop_keep_awake");
+}
+
+export async function getKeepAwakeStatus(): Promise<KeepAwakeStatus> {
+  return invoke<KeepAwakeStatus>("get_keep_awake_status");
+}
+
+export async function onKeepAwakeChange(
+  handler: (status: KeepAwakeStatus) => void
+): Promise<UnlistenFn> {
+  return listen<KeepAwakeStatus>("keep-awake://changed", (event) =>
+    handler(event.payload)
+  );
+}
+
 // Tray commands
 export type SystemStatus = "excellent" | "could-be-better" | "critical";
 
@@ -2,41 +2,86 @@ import { describe, it, expect, vi, beforeEach } from "vitest";
 import { useSystemStore } from "./systemStore";
 
 // Mock Tauri APIs
-vi.mock("../lib/tauri", () => ({
-  getRamInfo: vi.fn().mockResolvedValue({
-    total_bytes: 17179869184,
-    used_bytes: 10737418240,
-    available_bytes: 6442450944,
-    used_percentage: 62.5,
-    pressure_level: "normal",
-  }),
-  getCpuInfo: vi.fn().mockResolvedValue({
-    model_name: "Apple M3",
-    total_cores: 8,
-    total_usage_percentage: 25.0,
-    per_core_usage: [20, 30, 25, 22, 15, 18, 20, 25],
-    load_average: { one_minute: 1.5, five_minutes: 1.2, fifteen_minutes: 1.0 },
-  }),
-  getBatteryInfo: vi.fn().mockResolvedValue({
-    percentage: 85,
-    is_charging: false,
-    is_plugged_in: false,
-    power_source: "Battery",
-    condition: "Normal",
-    max_capacity_percentage: 96,
-    cycle_count: 150,
-  }),
-  getDiskInfo: vi.fn().mockResolvedValue({
-    primary: {
-      name: "Macintosh HD",
-      total_bytes: 500107862016,
-      available_bytes: 150000000000,
-      used_bytes: 350107862016,
-      used_percentage: 70,
-    },
-    total_used_percentage: 70,
-  }),
-}));
+vi.mock("../lib/tauri", () => {
+  const sample = <T>(data: T) => ({
+    timestamp_ms: 1700000000000,
+    data,
+    error: null,
+  });
+
+  return {
+    getSystemSnapshot: vi.fn().mockResolvedValue({
+      sequence: 1,
+      ram: sample({
+        total_bytes: 17179869184,
+        used_bytes: 10737418240,
+        available_bytes: 6442450944,
+        used_percentage: 62.5,
+        pressure_level: "normal",
+      }),
+      cpu: sample({
+        model_name: "Apple M3",
+        total_cores: 8,
+        physical_cores: null,
+        performance_cores: null,
+        efficiency_cores: null,
+        clusters: [],
+        cache: {
+          l1_instruction_bytes: null,
+          l1_data_bytes: null,
+          l2_bytes: null,
+          l3_bytes: null,
+        },
+        total_usage_percentage: 25.0,
+        is_estimate: false,
+        breakdown: null,
+        per_core_usage: [
+          { usage_percentage: 20, kind: null, frequency_mhz: null },
+          { usage_percentage: 30, kind: null, frequency_mhz: null },
+          { usage_percentage: 25, kind: null, frequency_mhz: null },
+          { usage_percentage: 22, kind: null, frequency_mhz: null },
+          { usage_percentage: 15, kind: null, frequency_mhz: null },
+          { usage_percentage: 18, kind: null, frequency_mhz: null },
+          { usage_percentage: 20, kind: null, frequency_mhz: null },
+          { usage_percentage: 25, kind: null, frequency_mhz: null },
+        ],
+        load_average: {
+          one_minute: 1.5,
+          five_minutes: 1.2,
+          fifteen_minutes: 1.0,
+        },
+        frequency: {
+          current_mhz: null,
+          nominal_mhz: null,
+          max_mhz: null,
+        },
+        thermal: null,
+      }),
+      battery: sample({
+        percentage: 85,
+        is_charging: false,
+        is_plugged_in: false,
+        power_source: "Battery",
+        condition: "Normal",
+        max_capacity_percentage: 96,
+        cycle_count: 150,
+      }),
+      disk: sample({
+        primary: {
+          name: "Macintosh HD",
+          total_bytes: 500107862016,
+          available_bytes: 150000000000,
+          used_bytes: 350107862016,
+          used_percentage: 70,
+        },
+        total_used_percentage: 70,
+      }),
+      top_memory_processes: sample([]),
+      top_cpu_processes: sample([]),
+      uptime_seconds: sample(3600),
+    }),
+  };
+});
 
 describe("systemStore", () => {
   beforeEach(() => {
@@ -49,6 +94,7 @@ describe("systemStore", () => {
       battery: null,
       disk: null,
       lastUpdated: null,
+      snapshotSequence: 0,
       isLoading: false,
       error: null,
       overallStatus: "excellent",
@@ -106,13 +152,40 @@ describe("systemStore", () => {
       const cpuData = {
         model_name: "Apple M3",
         total_cores: 8,
+        physical_cores: null,
+        performance_cores: null,
+        efficiency_cores: null,
+        clusters: [],
+        cache: {
+          l1_instruction_bytes: null,
+          l1_data_bytes: null,
+          l2_bytes: null,
+          l3_bytes: null,
+        },
         total_usage_percentage: 25.0,
-        per_core_usage: [20, 30, 25, 22, 15, 18, 20, 25],
+        is_estimate: false,
+        breakdown: null,
+        per_core_usage: [
+          { usage_percentage: 20, kind: null, frequency_mhz: null },
+          { usage_percentage: 30, kind: null, frequency_mhz: null },
+          { usage_percentage: 25, kind: null, frequency_mhz: null },
+          { usage_percentage: 22, kind: null, frequency_mhz: null },
+          { usage_percentage: 15, kind: null, frequency_mhz: null },
+          { usage_percentage: 18, kind: null, frequency_mhz: null },
+          { usage_percentage: 20, kind: null, frequency_mhz: null },
+          { usage_percentage: 25, kind: null, frequency_mhz: null },
+        ],
         load_average: {
           one_minute: 1.5,
           five_minutes: 1.2,
           fifteen_minutes: 1.0,
         },
+        frequency: {
+          current_mhz: null,
+          nominal_mhz: null,
+          max_mhz: null,
+        },
+        thermal: null,
       };
       store.setCpu(cpuData);
 
@@ -134,6 +207,13 @@ describe("systemStore", () => {
         time_to_empty_minutes: 180,
         temperature_celsius: null,
         voltage_volts: null,
+        amperage_ma: null,
+        power_draw_watts: null,
+        cell_voltages_mv: [],
+        cell_imbalance_mv: null,
+        adapter: null,
+        time_estimate: null,
+        low_power_mode: null,
       };
       store.setBattery(batteryData);
 
@@ -188,6 +268,15 @@ describe("systemStore", () => {
       expect(state.lastUpdated).not.toBeNull();
     });
 
+    it("ignores snapshots older than the last one applied", async () => {
+      useSystemStore.setState({ snapshotSequence: 5 });
+      await useSystemStore.getState().refreshAll();
+
+      const state = useSystemStore.getState();
+      expect(state.ram).toBeNull();
+      expect(state.isLoading).toBe(false);
+    });
+
     it("sets isLoading during fetch", async () => {
       const store = useSystemStore.getState();
       const promise = store.refreshAll();
@@ -200,6 +289,58 @@ describe("systemStore", () => {
     });
   });
 
+  describe("receiveSample", () => {
+    it("stores sampled data and its timestamp", () => {
+      const ramData = {
+        total_bytes: 17179869184,
+        used_bytes: 16500000000,
+        available_bytes: 679869184,
+        used_percentage: 96,
+        pressure_level: "critical" as const,
+      };
+      useSystemStore.getState().receiveSample("ram", {
+        timestamp_ms: 1700000000000,
+        data: ramData,
+        error: null,
+      });
+
+      const state = useSystemStore.getState();
+      expect(state.ram).toEqual(ramData);
+      expect(state.lastUpdated).toBe(1700000000000);
+      expect(state.overallStatus).toBe("critical");
+    });
+
+    it("clears battery when the machine has none", () => {
+      const consoleSpy = vi.spyOn(console, "error").mockImplementation(() => {});
+      useSystemStore.getState().receiveSample("battery", {
+        timestamp_ms: 1700000000000,
+        data: null,
+        error: {
+          code: "NoBattery",
+          message: "No battery found (desktop Mac?)",
+        },
+      });
+
+      expect(useSystemStore.getState().battery).toBeNull();
+      expect(useSystemStore.getState().error).toBeNull();
+      expect(consoleSpy).not.toHaveBeenCalled();
+      consoleSpy.mockRestore();
+    });
+
+    it("reports errors and keeps the last reading", () => {
+      useSystemStore.setState({ lastUpdated: 1 });
+      useSystemStore.getState().receiveSample("cpu", {
+        timestamp_ms: 1700000000000,
+        data: null,
+        error: { code: "Internal", message: "sampling failed" },
+      });
+
+      const state = useSystemStore.getState();
+      expect(state.error).toBe("sampling failed");
+      expect(state.lastUpdated).toBe(1);
+    });
+  });
+
   describe("calculateOverallStatus", () => {
     it("returns excellent when all metrics are good", () => {
       const store = useSystemStore.getState();
@@ -213,13 +354,31 @@ describe("systemStore", () => {
       store.setCpu({
         model_name: "Apple M3",
         total_cores: 8,
+        physical_cores: null,
+        performance_cores: null,
+        efficiency_cores: null,
+        clusters: [],
+        cache: {
+          l1_instruction_bytes: null,
+          l1_data_bytes: null,
+          l2_bytes: null,
+          l3_bytes: null,
+        },
         total_usage_percentage: 25.0,
+        is_estimate: false,
+        breakdown: null,
         per_core_usage: [],
         load_average: {
           one_minute: 1.0,
           five_minutes: 1.0,
           fifteen_minutes: 1.0,
         },
+        frequency: {
+          current_mhz: null,
+          nominal_mhz: null,
+          max_mhz: null,
+        },
+        thermal: null,
       });
 
       expect(useSystemStore.getState().overallStatus).toBe("excellent");
@@ -256,13 +415,31 @@ describe("systemStore", () => {
       store.setCpu({
         model_name: "Apple M3",
         total_cores: 8,
+        physical_cores: null,
+        performance_cores: null,
+        efficiency_cores: null,
+        clusters: [],
+        cache: {
+          l1_instruction_bytes: null,
+          l1_data_bytes: null,
+          l2_bytes: null,
+          l3_bytes: null,
+        },
         total_usage_percentage: 95.0,
+        is_estimate: false,
+        breakdown: null,
         per_core_usage: [],
         load_average: {
           one_minute: 5.0,
           five_minutes: 4.0,
           fifteen_minutes: 3.0,
         },
+        frequency: {
+          current_mhz: null,
+          nominal_mhz: null,
+          max_mhz: null,
+        },
+        thermal: null,
       });
 
       expect(useSystemStore.getState().overallStatus).toBe("critical");
@@ -273,13 +450,31 @@ describe("systemStore", () => {
       store.setCpu({
         model_name: "Apple M3",
         total_cores: 8,
+        physical_cores: null,
+        performance_cores: null,
+        efficiency_cores: null,
+        clusters: [],
+        cache: {
+          l1_instruction_bytes: null,
+          l1_data_bytes: null,
+          l2_bytes: null,
+          l3_bytes: null,
+        },
         total_usage_percentage: 80.0,
+        is_estimate: false,
+        breakdown: null,
         per_core_usage: [],
         load_average: {
           one_minute: 3.0,
           five_minutes: 2.5,
           fifteen_minutes: 2.0,
         },
+        frequency: {
+          current_mhz: null,
+          nominal_mhz: null,
+          max_mhz: null,
+        },
+        thermal: null,
       });
 
       expect(useSystemStore.getState().overallStatus).toBe("could-be-better");
@@ -299,6 +494,13 @@ describe("systemStore", () => {
         time_to_empty_minutes: 10,
         temperature_celsius: null,
         voltage_volts: null,
+        amperage_ma: null,
+        power_draw_watts: null,
+        cell_voltages_mv: [],
+        cell_imbalance_mv: null,
+        adapter: null,
+        time_estimate: null,
+        low_power_mode: null,
       });
 
       expect(useSystemStore.getState().overallStatus).toBe("critical");
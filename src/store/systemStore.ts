@@ -6,8 +6,12 @@ import type {
   CpuInfo,
   BatteryInfo,
   DisksOverview,
+  MetricKind,
+  MetricPayloads,
+  MetricSample,
 } from "../types";
 import * as tauri from "../lib/tauri";
+import { isMonitorError, errorMessage } from "../lib/errors";
 
 interface SystemState {
   // Navigation
@@ -22,6 +26,7 @@ interface SystemState {
 
   // Meta
   lastUpdated: number | null;
+  snapshotSequence: number;
   isLoading: boolean;
   error: string | null;
 
@@ -38,6 +43,10 @@ interface SystemActions {
   setDisk: (data: DisksOverview) => void;
   setError: (error: string | null) => void;
   refreshAll: () => Promise<void>;
+  receiveSample: <K extends MetricKind>(
+    metric: K,
+    sample: MetricSample<MetricPayloads[K]>
+  ) => void;
 }
 
 function calculateOverallStatus(state: {
@@ -93,6 +102,7 @@ export const useSystemStore = create<SystemState & SystemActions>((set) => ({
   battery: null,
   disk: null,
   lastUpdated: null,
+  snapshotSequence: 0,
   isLoading: false,
   error: null,
   overallStatus: "excellent",
@@ -152,39 +162,71 @@ export const useSystemStore = create<SystemState & SystemActions>((set) => ({
     set({ isLoading: true, error: null });
 
     try {
-      const [ram, cpu, disk] = await Promise.all([
-        tauri.getRamInfo(),
-        tauri.getCpuInfo(),
-        tauri.getDiskInfo(),
-      ]);
+      const snapshot = await tauri.getSystemSnapshot();
 
       // Battery might not be available on desktop Macs
-      let battery: BatteryInfo | null = null;
-      try {
-        battery = await tauri.getBatteryInfo();
-      } catch {
-        // Ignore battery errors (desktop Mac)
+      const batteryError = snapshot.battery.error;
+      if (
+        batteryError &&
+        !isMonitorError(batteryError, "NoBattery", "Unsupported")
+      ) {
+        console.error("Failed to get battery info:", batteryError);
       }
 
+      const sectionError =
+        snapshot.ram.error ?? snapshot.cpu.error ?? snapshot.disk.error;
+
       set((state) => {
+        // A slower, older snapshot must not overwrite a newer one
+        if (snapshot.sequence <= state.snapshotSequence) {
+          return { isLoading: false };
+        }
+
+        const ram = snapshot.ram.data ?? state.ram;
+        const cpu = snapshot.cpu.data ?? state.cpu;
+        const disk = snapshot.disk.data ?? state.disk;
+        const battery = snapshot.battery.data;
         const newState = { ...state, ram, cpu, battery, disk };
         return {
           ram,
           cpu,
           battery,
           disk,
+          snapshotSequence: snapshot.sequence,
           lastUpdated: Date.now(),
           isLoading: false,
+          error: sectionError ? sectionError.message : null,
           overallStatus: calculateOverallStatus(newState),
         };
       });
     } catch (e) {
       set({
-        error: e instanceof Error ? e.message : "Failed to fetch system info",
+        error: errorMessage(e, "Failed to fetch system info"),
         isLoading: false,
       });
     }
   },
+
+  receiveSample: (metric, sample) =>
+    set((state) => {
+      if (sample.error) {
+        // Battery might not be available on desktop Macs
+        if (metric === "battery") {
+          if (!isMonitorError(sample.error, "NoBattery", "Unsupported")) {
+            console.error("Failed to get battery info:", sample.error);
+          }
+        } else {
+          return { error: sample.error.message };
+        }
+      }
+
+      const newState = { ...state, [metric]: sample.data };
+      return {
+        [metric]: sample.data,
+        lastUpdated: sample.timestamp_ms,
+        overallStatus: calculateOverallStatus(newState),
+      };
+    }),
 }));
 
 // Selectors for optimized re-renders
@@ -1,3 +1,22 @@
+// Error types
+export type MonitorErrorCode =
+  | "NoBattery"
+  | "CommandFailed"
+  | "PermissionDenied"
+  | "ParseError"
+  | "Unsupported"
+  | "InvalidArgument"
+  | "NotFound"
+  | "Internal";
+
+export interface MonitorError {
+  code: MonitorErrorCode;
+  message: string;
+  // Only present for CommandFailed
+  tool?: string;
+  status?: number | null;
+}
+
 // View types
 export type ViewType = "dashboard" | "memory" | "storage" | "battery" | "cpu";
 export type SystemStatus = "excellent" | "could-be-better" | "critical";
@@ -34,9 +53,69 @@ export interface ForceQuitResult {
 export interface CpuInfo {
   model_name: string;
   total_cores: number;
+  physical_cores: number | null;
+  performance_cores: number | null;
+  efficiency_cores: number | null;
+  clusters: CpuCluster[];
+  cache: CpuCache;
   total_usage_percentage: number;
-  per_core_usage: number[];
+  is_estimate: boolean;
+  breakdown: CpuBreakdown | null;
+  per_core_usage: CoreUsage[];
   load_average: LoadAverage;
+  frequency: CpuFrequency;
+  thermal: ThermalState | null;
+}
+
+export interface CpuBreakdown {
+  user_percentage: number;
+  system_percentage: number;
+  idle_percentage: number;
+  nice_percentage: number | null;
+  iowait_percentage: number | null;
+  steal_percentage: number | null;
+}
+
+export type CoreKind = "performance" | "efficiency";
+
+export interface CpuCluster {
+  name: string;
+  kind: CoreKind;
+  physical_cores: number;
+  logical_cores: number;
+  cores_per_l2: number | null;
+  cache: CpuCache;
+}
+
+export interface CpuCache {
+  l1_instruction_bytes: number | null;
+  l1_data_bytes: number | null;
+  l2_bytes: number | null;
+  l3_bytes: number | null;
+}
+
+export interface CoreUsage {
+  usage_percentage: number;
+  kind: CoreKind | null;
+  frequency_mhz: number | null;
+}
+
+export interface CpuFrequency {
+  current_mhz: number | null;
+  nominal_mhz: number | null;
+  max_mhz: number | null;
+}
+
+export type ThermalPressure = "nominal" | "moderate" | "heavy" | "critical";
+
+export interface ThermalState {
+  pressure: ThermalPressure;
+  is_throttled: boolean;
+  cpu_speed_limit_percentage: number | null;
+  scheduler_limit_percentage: number | null;
+  available_cpus: number | null;
+  thermal_warning_level: number | null;
+  performance_warning_level: number | null;
 }
 
 export interface LoadAverage {
@@ -64,6 +143,103 @@ export interface BatteryInfo {
   time_to_empty_minutes: number | null;
   temperature_celsius: number | null;
   voltage_volts: number | null;
+  // Negative while discharging
+  amperage_ma: number | null;
+  power_draw_watts: number | null;
+  cell_voltages_mv: number[];
+  cell_imbalance_mv: number | null;
+  adapter: AdapterInfo | null;
+  time_estimate: TimeEstimate | null;
+  low_power_mode: boolean | null;
+}
+
+export interface BatteryDetails {
+  serial: string | null;
+  manufacturer: string | null;
+  device_name: string | null;
+  manufacture_date: string | null;
+  age_days: number | null;
+  design_capacity_mah: number | null;
+  full_charge_capacity_mah: number | null;
+  cycle_count: number | null;
+}
+
+export type SettingSource = "battery" | "ac";
+
+export interface LowPowerModeResult {
+  success: boolean;
+  message: string;
+}
+
+export type PowerSource = "internal_battery" | "ac" | "ups";
+
+export interface UpsInfo {
+  name: string;
+  percentage: number | null;
+  is_on_battery: boolean;
+  is_charging: boolean;
+  time_remaining_minutes: number | null;
+}
+
+export interface PowerSourceInfo {
+  source: PowerSource;
+  has_internal_battery: boolean;
+  ups: UpsInfo | null;
+}
+
+export type SleepEventType = "sleep" | "wake" | "dark_wake";
+
+export interface SleepEvent {
+  timestamp_ms: number;
+  type: SleepEventType;
+  reason: string;
+  percentage: number | null;
+}
+
+export interface SleepPeriod {
+  start_ms: number;
+  end_ms: number;
+  duration_minutes: number;
+  sleep_reason: string;
+  wake_reason: string;
+  dark_wake_count: number;
+  on_battery: boolean;
+  start_percentage: number | null;
+  end_percentage: number | null;
+  drain_percentage: number | null;
+  drain_per_hour_percentage: number | null;
+}
+
+export interface SleepTimeline {
+  events: SleepEvent[];
+  periods: SleepPeriod[];
+}
+
+export interface SleepAssertion {
+  pid: number;
+  process_name: string;
+  assertion_type: string;
+  name: string | null;
+  held_seconds: number | null;
+  prevents_display_sleep: boolean;
+}
+
+export interface TimeEstimate {
+  minutes: number;
+  is_charging: boolean;
+  confidence: "high" | "low";
+  rate_percentage_per_hour: number;
+}
+
+export interface AdapterInfo {
+  watts: number | null;
+  name: string | null;
+  description: string | null;
+  family: string | null;
+  voltage_volts: number | null;
+  current_amps: number | null;
+  is_wireless: boolean;
+  is_underpowered: boolean;
 }
 
 // Disk types
@@ -97,3 +273,95 @@ export interface StorageCategories {
   categories: StorageCategory[];
   total_categorized: number;
 }
+
+// Metric events pushed by the backend sampler
+export interface MetricPayloads {
+  ram: RamInfo;
+  cpu: CpuInfo;
+  disk: DisksOverview;
+  battery: BatteryInfo;
+}
+
+export type MetricKind = keyof MetricPayloads;
+
+export interface MetricSample<T> {
+  timestamp_ms: number;
+  data: T | null;
+  error: MonitorError | null;
+}
+
+// Snapshot types
+export interface SystemSnapshot {
+  sequence: number;
+  ram: MetricSample<RamInfo>;
+  cpu: MetricSample<CpuInfo>;
+  disk: MetricSample<DisksOverview>;
+  battery: MetricSample<BatteryInfo>;
+  top_memory_processes: MetricSample<ProcessMemoryInfo[]>;
+  top_cpu_processes: MetricSample<ProcessCpuInfo[]>;
+  uptime_seconds: MetricSample<number>;
+}
+
+// CPU history types
+/** "sample" has one slot per CPU sampling interval */
+export type CpuHistoryResolution = "sample" | "minute";
+
+export interface CpuHistoryPoint {
+  timestamp_ms: number;
+  /** 0 when no sample landed in the slot */
+  sample_count: number;
+  total_usage_percentage: number | null;
+  per_core_usage: number[];
+  /** null only for empty slots */
+  load_average: LoadAverage | null;
+}
+
+export interface CpuHistorySeries {
+  slot_ms: number;
+  points: CpuHistoryPoint[];
+}
+
+// Battery history types
+export interface BatteryHistorySample {
+  timestamp_ms: number;
+  percentage: number;
+  is_charging: boolean;
+  is_plugged_in: boolean;
+  power_draw_watts: number | null;
+  temperature_celsius: number | null;
+}
+
+export type BatterySessionKind = "charging" | "discharging";
+
+export interface BatterySession {
+  kind: BatterySessionKind;
+  start_ms: number;
+  end_ms: number;
+  duration_minutes: number;
+  start_percentage: number;
+  end_percentage: number;
+  average_watts: number | null;
+}
+
+export interface HealthSample {
+  timestamp_ms: number;
+  max_capacity_percentage: number;
+  cycle_count: number | null;
+}
+
+export interface HealthForecast {
+  samples: HealthSample[];
+  loss_per_month_percentage: number | null;
+  loss_per_100_cycles_percentage: number | null;
+  service_recommended_at_ms: number | null;
+  replace_soon_at_ms: number | null;
+}
+
+export type KeepAwakeMode = "display" | "idle" | "system";
+
+export interface KeepAwakeStatus {
+  active: boolean;
+  mode: KeepAwakeMode | null;
+  started_at_ms: number | null;
+  ends_at_ms: number | null;
+}